@@ -0,0 +1,49 @@
+//! Time to compute station uptime over generated input, once it's read.
+//!
+//! Run with `cargo bench --bench compute`. Throughput is reported in
+//! availability reports per second.
+
+use charger_uptime::{generate, BucketWidth, ComputeOptions, ConflictPolicy, Fleet, GenerateOptions};
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+
+fn generate_fleet(options: GenerateOptions) -> Fleet {
+    let mut input: Vec<u8> = Vec::new();
+    generate(&mut input, &options).unwrap();
+    Fleet::from_reader(input.as_slice()).unwrap()
+}
+
+/// 2,000 stations of 4 chargers, with a week of hourly reports each.
+fn fleet_options() -> GenerateOptions {
+    GenerateOptions { stations: 2_000, chargers_per_station: 4, reports_per_charger: 7 * 24, ..GenerateOptions::default() }
+}
+
+fn report_count(fleet: &Fleet) -> u64 {
+    fleet.charger_uptime_map().values().map(|reports| reports.len() as u64).sum()
+}
+
+fn bench_compute_availability(c: &mut Criterion) {
+    let fleet = generate_fleet(fleet_options());
+    let mut group = c.benchmark_group("compute_availability");
+    group.throughput(Throughput::Elements(report_count(&fleet)));
+    group.sample_size(20);
+    group.bench_function("serial", |b| b.iter(|| fleet.compute().unwrap()));
+    let parallel = ComputeOptions { jobs: 4, ..ComputeOptions::default() };
+    group.bench_function("jobs_4", |b| b.iter(|| fleet.compute_with_options(&parallel).unwrap()));
+    let daily = ComputeOptions { bucket_width: Some(BucketWidth::Day), ..ComputeOptions::default() };
+    group.bench_function("daily_buckets", |b| b.iter(|| fleet.compute_with_options(&daily).unwrap()));
+    group.finish();
+
+    // Shuffled input with overlaps and conflicts to combine
+    let fleet = generate_fleet(GenerateOptions { overlap_rate: 0.05, conflict_rate: 0.01, shuffle: true, ..fleet_options() });
+    let mut group = c.benchmark_group("compute_availability_conflicts");
+    group.throughput(Throughput::Elements(report_count(&fleet)));
+    group.sample_size(20);
+    for conflict_policy in [ConflictPolicy::DownWins, ConflictPolicy::LatestLineWins] {
+        let options = ComputeOptions { conflict_policy, ..ComputeOptions::default() };
+        group.bench_function(conflict_policy.name(), |b| b.iter(|| fleet.compute_with_options(&options).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_compute_availability);
+criterion_main!(benches);
@@ -0,0 +1,92 @@
+//! Throughput of reading input in the `[Stations]` /
+//! `[Charger Availability Reports]` format.
+//!
+//! Run with `cargo bench --bench parse`. Throughput is reported in lines per
+//! second, so the groups can be compared with each other.
+
+use std::hint::black_box;
+use std::io::BufRead;
+
+use charger_uptime::parser::{construct_maps, parse_charger_availability, parse_station};
+use charger_uptime::{generate, GenerateOptions, ReadOptions};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use regex::Regex;
+
+/// Generates input with about `report_count` availability reports, from
+/// stations of 4 chargers with a day of hourly reports each.
+fn generate_input(report_count: u32) -> String {
+    let options = GenerateOptions {
+        stations: (report_count / 4 / 24).max(1),
+        reports_per_charger: 24,
+        ..GenerateOptions::default()
+    };
+    let mut input: Vec<u8> = Vec::new();
+    generate(&mut input, &options).unwrap();
+    String::from_utf8(input).unwrap()
+}
+
+/// Parses a charger availability entry the way it was done before patterns
+/// were compiled once, compiling the regex for every line.
+fn parse_with_regex_per_line(line: &str) -> Option<(u32, u64, u64)> {
+    let re = Regex::new(r"(?<charger_id>\d+)\s+(?<start_time>\d+)\s+(?<end_time>\d+)\s*(?<up_status>\w*)").unwrap();
+    let captures = re.captures(line)?;
+    Some((captures["charger_id"].parse().ok()?, captures["start_time"].parse().ok()?, captures["end_time"].parse().ok()?))
+}
+
+fn report_lines(input: &str) -> Vec<&str> {
+    input.lines()
+        .skip_while(|line| *line != "[Charger Availability Reports]")
+        .skip(1)
+        .collect()
+}
+
+fn bench_parse_lines(c: &mut Criterion) {
+    let input = generate_input(100_000);
+    let lines = report_lines(&input);
+    let station_lines: Vec<&str> = input.lines().skip(1).take_while(|line| !line.is_empty()).collect();
+
+    let mut group = c.benchmark_group("parse_lines");
+    group.throughput(Throughput::Elements(lines.len() as u64));
+    group.bench_function("parse_charger_availability", |b| b.iter(|| {
+        for line in &lines {
+            black_box(parse_charger_availability(black_box(line)).unwrap());
+        }
+    }));
+    group.throughput(Throughput::Elements(station_lines.len() as u64));
+    group.bench_function("parse_station", |b| b.iter(|| {
+        for line in &station_lines {
+            black_box(parse_station(black_box(line)).unwrap());
+        }
+    }));
+    group.finish();
+
+    // The old parser is slow enough that a few thousand lines are plenty
+    let baseline_lines = &lines[..5_000];
+    let mut group = c.benchmark_group("parse_lines_baseline");
+    group.throughput(Throughput::Elements(baseline_lines.len() as u64));
+    group.sample_size(10);
+    group.bench_function("regex_per_line", |b| b.iter(|| {
+        for line in baseline_lines {
+            black_box(parse_with_regex_per_line(black_box(line)).unwrap());
+        }
+    }));
+    group.finish();
+}
+
+fn bench_construct_maps(c: &mut Criterion) {
+    let mut group = c.benchmark_group("construct_maps");
+    group.sample_size(10);
+    for report_count in [100_000, 2_000_000] {
+        let input = generate_input(report_count);
+        group.throughput(Throughput::Elements(input.lines().count() as u64));
+        group.bench_function(format!("{}_reports", report_count), |b| b.iter_batched(
+            || input.as_bytes(),
+            |bytes| construct_maps(bytes.lines(), &ReadOptions::default()).unwrap(),
+            BatchSize::LargeInput,
+        ));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_parse_lines, bench_construct_maps);
+criterion_main!(benches);
@@ -0,0 +1,26 @@
+/// Optional details of a charger, from the `[Charger Attributes]` section.
+///
+/// Only the power rating is used in computing uptime, for the power-weighted
+/// uptime of stations. The connector type and network are kept for reference.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChargerAttributes {
+    /// Rated power of the charger in kW. Always finite and above 0.
+    pub power_kw: f64,
+    /// Type of connector, such as `CCS1` or `J1772`.
+    pub connector_type: Option<String>,
+    /// Network the charger belongs to.
+    pub network: Option<String>
+}
+
+impl ChargerAttributes {
+
+    /// Creates a new `ChargerAttributes` with only a power rating.
+    /// ### Input:
+    /// - `power_kw`: Rated power in kW
+    ///
+    /// ### Output:
+    /// - `ChargerAttributes`: The constructed struct. No validation is done here.
+    pub fn new(power_kw: f64) -> ChargerAttributes {
+        ChargerAttributes { power_kw, connector_type: None, network: None }
+    }
+}
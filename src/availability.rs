@@ -0,0 +1,831 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+
+use crate::attributes::ChargerAttributes;
+use crate::bucket::{station_buckets, BucketWidth};
+use crate::conflict::{combine_with_policy, ConflictPolicy};
+use crate::connector::ConnectorRollup;
+use crate::error::UptimeError;
+use crate::exclusion::{Exclusion, ExclusionIndex, StationExclusions};
+use crate::metric::{power_weighted_up_time, ChargerTimes, StationMetric};
+use crate::percent::{PercentFormat, UptimePercent};
+use crate::report::{BucketUptime, ChargerUptime, ExcludedTime, StationStatus, StationUptime, UptimeReport};
+use crate::status::{status_durations, UpStatuses};
+use crate::time_range::TimeRange;
+use crate::window::{ReportingWindow, UnreportedTime};
+
+/// Options for computing uptime with `compute_availability`.
+#[derive(Clone, Debug, Default)]
+pub struct ComputeOptions {
+    /// How to handle overlapping reports of a charger that disagree on `up` status.
+    pub conflict_policy: ConflictPolicy,
+    /// Window to compute uptime over. Without one, uptime is computed over the
+    /// span of each station's and charger's own reports.
+    pub window: Option<ReportingWindow>,
+    /// Width of the buckets to split each station's timeline into, for an
+    /// uptime series in `UptimeReport::buckets`.
+    pub bucket_width: Option<BucketWidth>,
+    /// Precision and rounding of uptime percentages.
+    pub percent_format: PercentFormat,
+    /// How to report stations without availability data.
+    pub no_data_policy: NoDataPolicy,
+    /// Number of threads to compute stations on. 0 and 1 compute all stations
+    /// on the calling thread.
+    pub jobs: usize,
+    /// Charger statuses that count as up, for reports with a charger status
+    /// instead of a `true`/`false` up status.
+    pub up_statuses: UpStatuses,
+    /// How the up status of a charger's connectors rolls up to the charger.
+    pub connector_rollup: ConnectorRollup,
+    /// How the up time of a station is computed from its chargers.
+    pub station_metric: StationMetric
+}
+
+/// How to report a station that has no availability data: none of its
+/// chargers reported, or none of their reports is inside the reporting window.
+/// With `UnreportedTime::Down`, such a station is down over all of the window
+/// instead.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NoDataPolicy {
+    /// Leave the station out of the report.
+    #[default]
+    Omit,
+    /// Report the station with 0 percent uptime.
+    Zero,
+    /// Report the station with unknown uptime.
+    Unknown
+}
+
+impl NoDataPolicy {
+
+    /// Name of the policy, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            NoDataPolicy::Omit => "omit",
+            NoDataPolicy::Zero => "zero",
+            NoDataPolicy::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for NoDataPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for NoDataPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<NoDataPolicy, String> {
+        match name {
+            "omit" => Ok(NoDataPolicy::Omit),
+            "zero" => Ok(NoDataPolicy::Zero),
+            "unknown" => Ok(NoDataPolicy::Unknown),
+            _ => Err(format!("Unknown no data policy '{}'. Expected one of: omit, zero, unknown.", name)),
+        }
+    }
+}
+
+/// Takes in a station-charger map, and a charger-uptime map to compute
+/// availability percentage for each station
+///
+/// Stations are computed independently of each other, on `options.jobs`
+/// threads. The result is the same whatever the number of threads.
+///
+/// ### Input:
+/// - `station_charger_map`: A map of Station ID to IDs of chargers at the station
+/// - `charger_uptime_map`: A map of Charger ID to `TimeRange` structs for the charger
+/// - `options`: Options for the computation
+///
+/// ### Output:
+/// - `Result<UptimeReport, UptimeError>`: Uptime of each station wrapped in `Ok()`
+///   if successful and `UptimeError` in case of conflicting reports that the
+///   conflict policy doesn't resolve, for the lowest Station ID with any.
+///   Stations without reports are reported as set in the no data policy.
+pub fn compute_availability( station_charger_map: &HashMap<u32, HashSet<u32>>,
+                             charger_uptime_map: &HashMap<u32, Vec<TimeRange>>,
+                             options: &ComputeOptions)
+                             -> Result<UptimeReport, UptimeError> {
+    compute_availability_with_exclusions(station_charger_map, charger_uptime_map, &[], options)
+}
+
+/// Same as `compute_availability`, with excluded time taken out of the uptime
+/// of stations and chargers.
+///
+/// A charger's excluded time is the time excluded for it or for its station.
+/// A station's excluded time is the time excluded for it, or for all of its
+/// chargers. Excluded time counts as neither up nor reported, and is broken
+/// down by reason in `UptimeReport::excluded`.
+///
+/// Chargers have no power ratings here, so stations have no power-weighted
+/// uptime. Use `Fleet` to compute it with power ratings.
+pub fn compute_availability_with_exclusions( station_charger_map: &HashMap<u32, HashSet<u32>>,
+                                             charger_uptime_map: &HashMap<u32, Vec<TimeRange>>,
+                                             exclusions: &[Exclusion],
+                                             options: &ComputeOptions)
+                                             -> Result<UptimeReport, UptimeError> {
+    compute_fleet_availability(station_charger_map, charger_uptime_map, exclusions, &HashMap::new(), options)
+}
+
+/// Same as `compute_availability_with_exclusions`, with the power ratings in
+/// `charger_attributes` weighing chargers in the power-weighted uptime of
+/// stations.
+pub(crate) fn compute_fleet_availability( station_charger_map: &HashMap<u32, HashSet<u32>>,
+                                          charger_uptime_map: &HashMap<u32, Vec<TimeRange>>,
+                                          exclusions: &[Exclusion],
+                                          charger_attributes: &HashMap<u32, ChargerAttributes>,
+                                          options: &ComputeOptions)
+                                          -> Result<UptimeReport, UptimeError> {
+
+    // Computing in order of Station ID, so the same error is returned every time
+    let mut station_ids: Vec<u32> = station_charger_map.keys().copied().collect();
+    station_ids.sort();
+    let exclusion_index = ExclusionIndex::new(exclusions);
+    let compute = |station_id: &u32| {
+        let chargers = &station_charger_map[station_id];
+        compute_station(*station_id, chargers, charger_uptime_map, charger_attributes, &exclusion_index.station(*station_id, chargers), options)
+    };
+    let station_results: Vec<Result<StationResult, UptimeError>> = if options.jobs > 1 {
+        let thread_pool = ThreadPoolBuilder::new()
+            .num_threads(options.jobs)
+            .build()
+            .map_err(|pool_error| UptimeError::Io(io::Error::other(pool_error)))?;
+        thread_pool.install(|| station_ids.par_iter().map(compute).collect())
+    } else {
+        station_ids.iter().map(compute).collect()
+    };
+
+    let mut station_availability : Vec<StationUptime> = Vec::new();
+    let mut charger_availability : Vec<ChargerUptime> = Vec::new();
+    let mut bucket_availability : Vec<BucketUptime> = Vec::new();
+    let mut excluded_time : Vec<ExcludedTime> = Vec::new();
+    let mut conflicts_resolved: BTreeMap<u32, usize> = BTreeMap::new();
+    for station_result in station_results {
+        let station_result = station_result?;
+        station_availability.extend(station_result.station);
+        charger_availability.extend(station_result.chargers);
+        bucket_availability.extend(station_result.buckets);
+        excluded_time.extend(station_result.excluded);
+        conflicts_resolved.extend(station_result.conflicts_resolved);
+    }
+    let mut report = UptimeReport::new(station_availability);
+    report.set_chargers(charger_availability);
+    report.set_buckets(bucket_availability);
+    report.set_excluded(excluded_time);
+    report.conflicts_resolved = conflicts_resolved;
+    Ok(report)
+}
+
+/// Uptime of a single station, its chargers, and its buckets.
+struct StationResult {
+    station: Option<StationUptime>,
+    chargers: Vec<ChargerUptime>,
+    buckets: Vec<BucketUptime>,
+    excluded: Vec<ExcludedTime>,
+    /// Number of conflicting overlaps resolved, by Charger ID.
+    conflicts_resolved: Vec<(u32, usize)>
+}
+
+/// Computes the uptime of a single station from the reports of its chargers.
+/// ### Input:
+/// - `station_id`: ID of the station
+/// - `chargers`: IDs of chargers at the station
+/// - `charger_uptime_map`: A map of Charger ID to `TimeRange` structs for the charger
+/// - `charger_attributes`: A map of Charger ID to attributes of the charger
+/// - `exclusions`: Excluded time of the station and its chargers
+/// - `options`: Options for the computation
+///
+/// ### Output:
+/// - `Result<StationResult, UptimeError>`: The station's uptime wrapped in `Ok()`
+///   if successful and `UptimeError` in case of conflicting reports that the
+///   conflict policy doesn't resolve, for the lowest Charger ID with any.
+fn compute_station(station_id: u32, chargers: &HashSet<u32>, charger_uptime_map: &HashMap<u32, Vec<TimeRange>>,
+                   charger_attributes: &HashMap<u32, ChargerAttributes>, exclusions: &StationExclusions, options: &ComputeOptions) -> Result<StationResult, UptimeError> {
+
+    let mut charger_ids: Vec<u32> = chargers.iter().copied().collect();
+    charger_ids.sort();
+    let mut station_result = StationResult {
+        station: None,
+        chargers: Vec::new(),
+        buckets: Vec::new(),
+        excluded: Vec::new(),
+        conflicts_resolved: Vec::new(),
+    };
+
+    // Gathering all charger reportings of a station, without excluded time,
+    // and the span they were reported over. Every charger is kept on its own
+    // for the station metric.
+    let mut station_reported_time: Vec<TimeRange> = Vec::new();
+    let mut station_chargers: Vec<ChargerTimes> = Vec::with_capacity(charger_ids.len());
+    let mut station_span: Option<(u64, u64)> = None;
+    for charger in charger_ids {
+        station_chargers.push(ChargerTimes {
+            power_kw: charger_attributes.get(&charger).map(|attributes| attributes.power_kw),
+            times: Vec::new(),
+        });
+        let charger_times = charger_uptime_map.get(&charger);
+        if charger_times.is_none() {
+            continue;
+        }
+
+        let charger_times = options.up_statuses.apply_all(charger_times.unwrap());
+        let (mut charger_reports, conflicts) = combine_with_policy(charger, &charger_times, options.conflict_policy)?;
+        if conflicts>0 {
+            station_result.conflicts_resolved.push((charger, conflicts));
+        }
+        if let Some(window) = &options.window {
+            charger_reports = window.clip(&charger_reports);
+        }
+        // Combined reports are sorted. Skipping chargers without any
+        // report in the window.
+        let charger_summary = summarize_timeline(&charger_reports);
+        if charger_summary.is_none() {
+            continue;
+        }
+        let charger_exclusions = exclusions.charger(charger);
+        let charger_reports = charger_exclusions.remove_from(charger_reports);
+        let charger_summary = charger_exclusions.exclude(charger_summary.unwrap(), &charger_reports, options.window.as_ref());
+        station_span = Some(station_span.map_or(
+            (charger_summary.first_reported_time, charger_summary.last_reported_time),
+            |(first, last)| (first.min(charger_summary.first_reported_time), last.max(charger_summary.last_reported_time))));
+        let status_nanos = status_durations(&charger_times, &charger_reports);
+        station_result.chargers.push(ChargerUptime {
+            charger_id: charger,
+            station_id,
+            uptime_percent: (!charger_summary.is_all_excluded()).then(|| charger_summary.uptime_percent(options.percent_format)),
+            reported_nanos: charger_summary.reported_time,
+            up_nanos: charger_summary.up_time,
+            status_nanos,
+            excluded_nanos: charger_summary.excluded_time,
+        });
+        station_reported_time.extend(charger_reports.iter().cloned());
+        if let Some(charger_times) = station_chargers.last_mut() {
+            charger_times.times = charger_reports;
+        }
+    }
+
+    // Unreported time inside the window counting as down, a station without
+    // reports in it was down all of the window.
+    let station_span = station_span.or_else(|| options.window.as_ref()
+        .filter(|window| window.unreported == UnreportedTime::Down)
+        .map(|window| (window.from, window.to)));
+    let Some((first_reported_time, last_reported_time)) = station_span else {
+        // No charger reported in from this station.
+        station_result.station = station_uptime(station_id, None, chargers.len(), options);
+        return Ok(station_result);
+    };
+
+    // Sort in ascending order of 'from time' of availability report
+    station_reported_time.sort();
+
+    let station_summary = TimelineSummary {
+        first_reported_time,
+        last_reported_time,
+        reported_time: last_reported_time - first_reported_time,
+        up_time: options.station_metric.up_time(&station_chargers, first_reported_time, last_reported_time),
+        excluded_time: 0,
+    };
+    let station_summary = apply_window(station_summary, &station_reported_time, options.window.as_ref());
+    let station_summary = exclusions.station.exclude_span(station_summary, options.window.as_ref());
+    if let Some(bucket_width) = options.bucket_width {
+        station_result.buckets = station_buckets(station_id, &station_reported_time, &station_summary, &station_chargers, &exclusions.station, options, bucket_width);
+    }
+    station_result.excluded = exclusions.station.reasons_within(station_summary.first_reported_time, station_summary.last_reported_time).into_iter()
+        .map(|(reason, excluded_nanos)| ExcludedTime { station_id, reason, excluded_nanos })
+        .collect();
+    station_result.station = station_uptime(station_id, Some(&station_summary), chargers.len(), options).map(|mut station| {
+        if station.status == StationStatus::NoData {
+            return station;
+        }
+        // Up time can't exceed the reported time, whatever the rounding
+        station.power_weighted_up_nanos = power_weighted_up_time(&station_chargers, first_reported_time, last_reported_time)
+            .map(|up_time| up_time.min(station_summary.reported_time));
+        station.power_weighted_uptime_percent = station.power_weighted_up_nanos
+            .map(|up_time| UptimePercent::new(up_time, station_summary.reported_time, options.percent_format));
+        station
+    });
+    Ok(station_result)
+}
+
+/// Uptime of a station from the summary of its chargers' reports.
+/// ### Input:
+/// - `station_id`: ID of the station
+/// - `summary`: Summary of the station's reports with the reporting window
+///   applied, or `None` if none of its chargers reported. A station whose
+///   reported time was all excluded has no data either.
+/// - `charger_count`: Number of chargers at the station
+/// - `options`: Options of the computation
+///
+/// ### Output:
+/// - `Option<StationUptime>`: Uptime of the station. Stations without reports
+///   are reported as set in the no data policy, and left out with `NoDataPolicy::Omit`.
+pub(crate) fn station_uptime(station_id: u32, summary: Option<&TimelineSummary>, charger_count: usize,
+                             options: &ComputeOptions) -> Option<StationUptime> {
+
+    let Some(summary) = summary.filter(|summary| !summary.is_all_excluded()) else {
+        let uptime_percent = match options.no_data_policy {
+            NoDataPolicy::Omit => return None,
+            NoDataPolicy::Zero => Some(UptimePercent::new(0, 0, options.percent_format)),
+            NoDataPolicy::Unknown => None,
+        };
+        return Some(StationUptime {
+            station_id,
+            status: StationStatus::NoData,
+            uptime_percent,
+            up_nanos: 0,
+            reported_nanos: 0,
+            first_reported_nanos: summary.map_or(0, |summary| summary.first_reported_time),
+            last_reported_nanos: summary.map_or(0, |summary| summary.last_reported_time),
+            charger_count,
+            excluded_nanos: summary.map_or(0, |summary| summary.excluded_time),
+            power_weighted_uptime_percent: None,
+            power_weighted_up_nanos: None,
+        });
+    };
+    Some(StationUptime {
+        station_id,
+        status: StationStatus::Ok,
+        uptime_percent: Some(summary.uptime_percent(options.percent_format)),
+        up_nanos: summary.up_time,
+        reported_nanos: summary.reported_time,
+        first_reported_nanos: summary.first_reported_time,
+        last_reported_nanos: summary.last_reported_time,
+        charger_count,
+        excluded_nanos: summary.excluded_time,
+        power_weighted_uptime_percent: None,
+        power_weighted_up_nanos: None,
+    })
+}
+
+/// Reported span and up time of a list of windows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct TimelineSummary {
+    /// Start of the earliest window.
+    pub first_reported_time: u64,
+    /// End of the latest window.
+    pub last_reported_time: u64,
+    /// Time uptime is computed over. By default, the time from the start of
+    /// the earliest window to the end of the latest one, where gaps in between
+    /// count as reported, down time.
+    pub reported_time: u64,
+    /// Time covered by at least one window with `up` status.
+    pub up_time: u64,
+    /// Excluded time inside the reported span, which is left out of both
+    /// `reported_time` and `up_time`.
+    pub excluded_time: u64
+}
+
+impl TimelineSummary {
+
+    /// Uptime percentage in the range [0-100], computed exactly and rounded
+    /// as set in `format`.
+    pub fn uptime_percent(&self, format: PercentFormat) -> UptimePercent {
+        UptimePercent::new(self.up_time, self.reported_time, format)
+    }
+
+    /// Whether all of the reported time was excluded, which leaves no time to
+    /// compute uptime over.
+    pub fn is_all_excluded(&self) -> bool {
+        self.reported_time == 0 && self.excluded_time > 0
+    }
+}
+
+/// Sweeps over a sorted list of windows, possibly overlapping, to find the
+/// reported span and the time covered by at least one `up` window.
+///
+/// ### Input:
+/// - `sorted_times`: `TimeRange`s sorted in ascending order of 'from time'
+///
+/// ### Output:
+/// - `Option<TimelineSummary>`: The summary, or `None` if there are no windows.
+pub(crate) fn summarize_timeline(sorted_times: &[TimeRange]) -> Option<TimelineSummary> {
+
+    let first_report = sorted_times.first()?;
+    let first_reported_time = first_report.from;
+    let mut last_reported_time = first_report.to;
+    let mut available_time: u64 = 0;
+    // End of the up time counted so far
+    let mut reported_till_time: u64 = first_reported_time;
+
+    for charger_time in sorted_times {
+
+        // Keep track of ending of reported time
+        if last_reported_time<charger_time.to {
+            last_reported_time = charger_time.to;
+        }
+
+        if !charger_time.up {
+            // charger is unavailable
+            continue;
+        }
+        // charger_time window already covered in previous window
+        if reported_till_time >= charger_time.to {
+            continue;
+        }
+        // charger_time window partial overlap in previous window
+        if reported_till_time >=charger_time.from {
+            available_time += charger_time.to-reported_till_time;
+        } else {
+            available_time += charger_time.to - charger_time.from;
+        }
+        reported_till_time = charger_time.to;
+    }
+    Some(TimelineSummary {
+        first_reported_time,
+        last_reported_time,
+        reported_time: last_reported_time - first_reported_time,
+        up_time: available_time,
+        excluded_time: 0,
+    })
+}
+
+/// Adjusts the summary of windows clipped to a reporting window, so that
+/// unreported time inside it counts as set in the reporting window.
+///
+/// ### Input:
+/// - `summary`: Summary of `sorted_times`
+/// - `sorted_times`: `TimeRange`s clipped to `window`, sorted in ascending order of 'from time'
+/// - `window`: The reporting window, if any
+///
+/// ### Output:
+/// - `TimelineSummary`: With `UnreportedTime::Down`, the summary spans the whole
+///   window. With `UnreportedTime::Unknown`, only time covered by a window counts
+///   as reported. Without a reporting window, the summary is unchanged.
+pub(crate) fn apply_window(summary: TimelineSummary, sorted_times: &[TimeRange], window: Option<&ReportingWindow>) -> TimelineSummary {
+    apply_window_with(summary, window, || covered_time(sorted_times))
+}
+
+/// Same as `apply_window`, for windows that are no longer held in a list.
+/// `covered_time` returns the time covered by at least one of the windows,
+/// and is only called with `UnreportedTime::Unknown`.
+pub(crate) fn apply_window_with<F>(mut summary: TimelineSummary, window: Option<&ReportingWindow>, covered_time: F) -> TimelineSummary
+    where F: FnOnce() -> u64 {
+
+    match window {
+        None => {},
+        Some(window) if window.unreported == UnreportedTime::Down => {
+            summary.first_reported_time = window.from;
+            summary.last_reported_time = window.to;
+            summary.reported_time = window.duration();
+        },
+        Some(_) => summary.reported_time = covered_time(),
+    }
+    summary
+}
+
+/// Time covered by at least one of the windows, up or down.
+pub(crate) fn covered_time(sorted_times: &[TimeRange]) -> u64 {
+
+    let mut covered: u64 = 0;
+    let mut covered_till_time: u64 = 0;
+    for time_range in sorted_times {
+        let from = time_range.from.max(covered_till_time);
+        if time_range.to > from {
+            covered += time_range.to - from;
+            covered_till_time = time_range.to;
+        }
+    }
+    covered
+}
+
+/// Merges a sorted list of a charger's reports into non-overlapping windows.
+/// Overlapping windows with the same `up` status are combined into one.
+/// Overlapping windows that disagree on `up` status are an error; see
+/// `combine_with_policy` to resolve them instead.
+///
+/// ### Input:
+/// - `charger_id`: ID of the charger, used for error reporting
+/// - `charger_times`: `TimeRange`s of a single charger, sorted in ascending order
+///
+/// ### Output:
+/// - `Result<Vec<TimeRange>, UptimeError>`: The combined windows wrapped in `Ok()` if
+///   successful, empty for empty input, and `UptimeError::ConflictingReports`
+///   if two overlapping windows disagree on `up` status.
+pub fn charger_times_combine(charger_id: u32, charger_times: &[TimeRange]) -> Result<Vec<TimeRange>, UptimeError> {
+    let mut condensed_times: Vec<TimeRange> = Vec::new();
+    let Some(first_report) = charger_times.first() else {
+        return Ok(condensed_times);
+    };
+    let mut curr_report = first_report.clone();
+    for curr in charger_times.iter().skip(1) {
+        if curr.from<curr_report.to {
+            if curr.up == curr_report.up {
+                curr_report.to = curr_report.to.max(curr.to);
+            } else {
+                return Err(UptimeError::ConflictingReports {
+                    charger_id,
+                    first: curr_report,
+                    second: curr.clone(),
+                });
+            }
+        } else {
+            condensed_times.push(curr_report);
+            curr_report = curr.clone();
+        }
+    }
+    condensed_times.push(curr_report);
+    Ok(condensed_times)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::exclusion::ExclusionTarget;
+    use crate::status::ChargerStatus;
+
+    #[test]
+    fn summarize_ignores_down_windows_for_up_time() {
+        // A down report of one charger overlapping an up report of another
+        let station_times = vec![TimeRange::new(0, 100, false), TimeRange::new(50, 150, true)];
+        let summary = summarize_timeline(&station_times).unwrap();
+        assert_eq!(summary.up_time, 100);
+        assert_eq!(summary.reported_time, 150);
+        assert_eq!(summary.uptime_percent(PercentFormat::default()), 66);
+    }
+
+    #[test]
+    fn summarize_zero_length_reports() {
+        let summary = summarize_timeline(&[TimeRange::new(2, 2, true)]).unwrap();
+        assert_eq!(summary.reported_time, 0);
+        assert_eq!(summary.uptime_percent(PercentFormat::default()), 0);
+        assert!(summarize_timeline(&[]).is_none());
+    }
+
+    #[test]
+    fn compute_per_charger_uptime() {
+        let station_charger_map = HashMap::from([(0, HashSet::from([1001, 1002]))]);
+        let charger_uptime_map = HashMap::from([
+            (1001, vec![TimeRange::new(0, 50, true), TimeRange::new(50, 100, false)]),
+            (1002, vec![TimeRange::new(50, 100, true)]),
+        ]);
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &ComputeOptions::default()).unwrap();
+        assert_eq!(report.get(0).unwrap().uptime_percent, Some(UptimePercent::from(100)));
+        assert_eq!(report.chargers, vec![
+            ChargerUptime { charger_id: 1001, station_id: 0, uptime_percent: Some(UptimePercent::from(50)), reported_nanos: 100, up_nanos: 50, status_nanos: BTreeMap::new(), excluded_nanos: 0 },
+            ChargerUptime { charger_id: 1002, station_id: 0, uptime_percent: Some(UptimePercent::from(100)), reported_nanos: 50, up_nanos: 50, status_nanos: BTreeMap::new(), excluded_nanos: 0 },
+        ]);
+    }
+
+    #[test]
+    fn compute_over_reporting_window() {
+        let station_charger_map = HashMap::from([(0, HashSet::from([1001, 1002])), (1, HashSet::from([1003]))]);
+        let charger_uptime_map = HashMap::from([
+            (1001, vec![TimeRange::new(0, 150, true)]),
+            (1002, vec![TimeRange::new(160, 180, false)]),
+            (1003, vec![TimeRange::new(0, 50, true)]),
+        ]);
+        let mut options = ComputeOptions {
+            window: Some(ReportingWindow::new(100, 200, UnreportedTime::Down).unwrap()),
+            ..ComputeOptions::default()
+        };
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        let station = report.get(0).unwrap();
+        assert_eq!((station.up_nanos, station.reported_nanos, station.uptime_percent), (50, 100, Some(UptimePercent::from(50))));
+        assert_eq!((station.first_reported_nanos, station.last_reported_nanos), (100, 200));
+        // Station 1 has no reports in the window, so it was down all of it
+        let station = report.get(1).unwrap();
+        assert_eq!((station.status, station.up_nanos, station.reported_nanos), (StationStatus::Ok, 0, 100));
+        assert_eq!(station.uptime_percent, Some(UptimePercent::from(0)));
+        assert_eq!(report.chargers.len(), 2);
+        // Excluded time is left out of it, whatever the no data policy
+        let exclusions = [Exclusion::new(ExclusionTarget::Station(1), 150, 200, "utility_outage")];
+        options.no_data_policy = NoDataPolicy::Unknown;
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        let station = report.get(1).unwrap();
+        assert_eq!((station.up_nanos, station.reported_nanos, station.excluded_nanos), (0, 50, 50));
+        assert_eq!(report.to_string(), "0 50\n1 0");
+
+        options.window = Some(ReportingWindow::new(100, 200, UnreportedTime::Unknown).unwrap());
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        let station = report.get(0).unwrap();
+        assert_eq!((station.up_nanos, station.reported_nanos, station.uptime_percent), (50, 70, Some(UptimePercent::from(71))));
+    }
+
+    #[test]
+    fn compute_with_charger_statuses() {
+        let station_charger_map = HashMap::from([(0, HashSet::from([1001]))]);
+        let charger_uptime_map = HashMap::from([(1001, vec![
+            TimeRange::with_status(0, 40, ChargerStatus::Available),
+            TimeRange::with_status(40, 60, ChargerStatus::Reserved),
+            TimeRange::with_status(60, 100, ChargerStatus::Faulted),
+            TimeRange::new(100, 120, true),
+        ])]);
+        let mut options = ComputeOptions::default();
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        assert_eq!(report.get(0).unwrap().up_nanos, 80);
+        assert_eq!(report.charger_lines(), "1001 0 66 120 80 available=40 faulted=40 reserved=20");
+
+        // Reserved time doesn't count as up, but is still broken down
+        options.up_statuses = UpStatuses::new(&[ChargerStatus::Available]);
+        options.window = Some(ReportingWindow::new(50, 150, UnreportedTime::Unknown).unwrap());
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        assert_eq!(report.get(0).unwrap().up_nanos, 20);
+        assert_eq!(report.charger_lines(), "1001 0 28 70 20 faulted=40 reserved=10");
+    }
+
+    #[test]
+    fn compute_with_station_metrics() {
+        let station_charger_map = HashMap::from([(0, HashSet::from([1001, 1002, 1003, 1004]))]);
+        let charger_uptime_map = HashMap::from([
+            (1001, vec![TimeRange::new(0, 100, true)]),
+            (1002, vec![TimeRange::new(0, 50, true), TimeRange::new(50, 100, false)]),
+            (1003, vec![TimeRange::new(25, 100, true), TimeRange::new(0, 25, true)]),
+        ]);
+        let uptimes: Vec<String> = [StationMetric::AnyUp, StationMetric::AllUp, StationMetric::AtLeast(3), StationMetric::PortWeighted].into_iter()
+            .map(|station_metric| {
+                let options = ComputeOptions { station_metric, bucket_width: Some(BucketWidth::Nanos(50)), ..ComputeOptions::default() };
+                let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+                let bucket_up_nanos: u64 = report.buckets.iter().map(|bucket| bucket.up_nanos).sum();
+                assert_eq!(bucket_up_nanos, report.get(0).unwrap().up_nanos);
+                report.to_string()
+            })
+            .collect();
+        // 1004 never reported, so it's left out
+        assert_eq!(uptimes, vec!["0 100", "0 50", "0 50", "0 83"]);
+    }
+
+    #[test]
+    fn compute_power_weighted() {
+        let input = "[Stations]\n0 1001 1002 1003\n1 2001 2002\n\n[Charger Attributes]\n1001 350 CCS1 electrify\n1002 50\n2001 50\n\n\
+            [Charger Availability Reports]\n1001 0 100 false\n1002 0 100 true\n2001 0 100 false\n2002 0 100 true\n";
+        let fleet = crate::Fleet::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(fleet.charger_attributes()[&1001].connector_type.as_deref(), Some("CCS1"));
+        let options = ComputeOptions { station_metric: StationMetric::PortWeighted, ..ComputeOptions::default() };
+        let report = fleet.compute_with_options(&options).unwrap();
+        // Reported next to the station metric. 1003 has no power rating, but
+        // never reported, so it doesn't count.
+        let station = report.get(0).unwrap();
+        assert_eq!((station.up_nanos, station.power_weighted_up_nanos), (50, Some(12)));
+        // 2002 reported without a power rating, so station 1 is unrated
+        let station = report.get(1).unwrap();
+        assert_eq!((station.up_nanos, station.power_weighted_up_nanos, station.power_weighted_uptime_percent), (50, None, None));
+        assert_eq!(report.to_string(), "0 50 power_weighted=12\n1 50");
+        // Without any power ratings, no station is rated
+        let report = compute_availability(fleet.station_charger_map(), fleet.charger_uptime_map(), &options).unwrap();
+        assert!(report.stations.iter().all(|station| station.power_weighted_up_nanos.is_none()));
+        assert_eq!(report.to_string(), "0 50\n1 50");
+    }
+
+    #[test]
+    fn compute_with_exclusions() {
+        let station_charger_map = HashMap::from([(0, HashSet::from([1001, 1002]))]);
+        let charger_uptime_map = HashMap::from([
+            (1001, vec![TimeRange::new(0, 100, true)]),
+            (1002, vec![TimeRange::new(0, 50, true), TimeRange::new(50, 100, false)]),
+        ]);
+        let exclusions = [
+            Exclusion::new(ExclusionTarget::Charger(1001), 20, 40, "maintenance"),
+            Exclusion::new(ExclusionTarget::Station(0), 80, 100, "utility_outage"),
+        ];
+        let mut options = ComputeOptions::default();
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        let station = report.get(0).unwrap();
+        assert_eq!((station.up_nanos, station.reported_nanos, station.excluded_nanos), (80, 80, 20));
+        assert_eq!(report.charger_lines(), "1001 0 100 60 60\n1002 0 62 80 50");
+        assert_eq!(report.chargers[0].excluded_nanos, 40);
+        assert_eq!(report.excluded_lines(), "0 utility_outage 20");
+
+        // Excluding one charger leaves the time of the others in the station
+        let charger_uptime_map = HashMap::from([
+            (1001, vec![TimeRange::new(0, 100, true)]),
+            (1002, vec![TimeRange::new(0, 100, false)]),
+        ]);
+        let exclusions = [Exclusion::new(ExclusionTarget::Charger(1002), 0, 100, "maintenance")];
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        let station = report.get(0).unwrap();
+        assert_eq!((station.up_nanos, station.reported_nanos, station.excluded_nanos), (100, 100, 0));
+        assert_eq!(report.to_string(), "0 100");
+        assert_eq!(report.excluded_lines(), "");
+        // Only time when both chargers are excluded is left out of the station
+        let exclusions = [
+            Exclusion::new(ExclusionTarget::Charger(1001), 20, 60, "maintenance"),
+            Exclusion::new(ExclusionTarget::Charger(1002), 40, 80, "maintenance"),
+        ];
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        let station = report.get(0).unwrap();
+        assert_eq!((station.up_nanos, station.reported_nanos, station.excluded_nanos), (60, 80, 20));
+        assert_eq!(report.excluded_lines(), "0 maintenance 20");
+
+        // All of the reported time excluded leaves no data, not 0% uptime
+        let exclusions = [Exclusion::new(ExclusionTarget::Station(0), 0, 100, "utility_outage")];
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        assert!(report.get(0).is_none());
+        options.no_data_policy = NoDataPolicy::Unknown;
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        let station = report.get(0).unwrap();
+        assert_eq!((station.status, station.uptime_percent, station.excluded_nanos), (StationStatus::NoData, None, 100));
+        assert_eq!(report.to_string(), "0 unknown");
+        assert_eq!(report.charger_lines(), "1001 0 unknown 0 0\n1002 0 unknown 0 0");
+        assert_eq!(report.excluded_lines(), "0 utility_outage 100");
+        options.no_data_policy = NoDataPolicy::Omit;
+
+        // Excluded time inside the window isn't down, even where nothing was reported
+        let exclusions = [Exclusion::new(ExclusionTarget::Station(0), 80, 150, "utility_outage")];
+        options.window = Some(ReportingWindow::new(0, 200, UnreportedTime::Down).unwrap());
+        options.bucket_width = Some(BucketWidth::Nanos(100));
+        let report = compute_availability_with_exclusions(&station_charger_map, &charger_uptime_map, &exclusions, &options).unwrap();
+        assert_eq!(report.to_string(), "0 61");
+        let bucket_values: Vec<(u64, u64, u64)> = report.buckets.iter()
+            .map(|bucket| (bucket.bucket_start_nanos, bucket.up_nanos, bucket.reported_nanos))
+            .collect();
+        assert_eq!(bucket_values, vec![(0, 80, 80), (100, 0, 50)]);
+        assert_eq!(report.excluded_lines(), "0 utility_outage 70");
+    }
+
+    #[test]
+    fn stations_without_data() {
+        let station_charger_map = HashMap::from([(0, HashSet::from([1001])), (1, HashSet::from([1002])), (2, HashSet::new())]);
+        let charger_uptime_map = HashMap::from([(1001, vec![TimeRange::new(0, 50, true)])]);
+        let mut options = ComputeOptions::default();
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        assert_eq!(report.to_string(), "0 100");
+
+        options.no_data_policy = NoDataPolicy::Zero;
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        assert_eq!(report.to_string(), "0 100\n1 0\n2 0");
+        assert_eq!(report.get(1).unwrap().status, StationStatus::NoData);
+        assert_eq!(report.get(0).unwrap().status, StationStatus::Ok);
+
+        options.no_data_policy = NoDataPolicy::Unknown;
+        let report = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        assert_eq!(report.to_string(), "0 100\n1 unknown\n2 unknown");
+        assert_eq!(report.get(2).unwrap().uptime_percent, None);
+        assert_eq!("zero".parse::<NoDataPolicy>(), Ok(NoDataPolicy::Zero));
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let mut station_charger_map: HashMap<u32, HashSet<u32>> = HashMap::new();
+        let mut charger_uptime_map: HashMap<u32, Vec<TimeRange>> = HashMap::new();
+        for station_id in 0..200u32 {
+            let chargers = [station_id * 10, station_id * 10 + 1];
+            station_charger_map.insert(station_id, HashSet::from(chargers));
+            for (i, charger) in chargers.iter().enumerate() {
+                let offset = (station_id as u64 * 37 + i as u64 * 11) % 100;
+                charger_uptime_map.insert(*charger, vec![
+                    TimeRange::new(offset, offset + 50, true),
+                    TimeRange::new(offset + 40, offset + 120, station_id % 3 == 0),
+                ]);
+            }
+        }
+        let mut options = ComputeOptions {
+            conflict_policy: ConflictPolicy::DownWins,
+            bucket_width: Some(BucketWidth::Nanos(30)),
+            ..ComputeOptions::default()
+        };
+        let serial = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        options.jobs = 4;
+        let parallel = compute_availability(&station_charger_map, &charger_uptime_map, &options).unwrap();
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel.conflicts_resolved.len(), 266);
+
+        // The conflict of the lowest Station ID fails the computation
+        options.conflict_policy = ConflictPolicy::Fail;
+        match compute_availability(&station_charger_map, &charger_uptime_map, &options) {
+            Err(UptimeError::ConflictingReports { charger_id, .. }) => assert_eq!(charger_id, 10),
+            other => panic!("Expected conflicting reports, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn combine_merges_overlapping_same_status() {
+        let charger_times = vec![
+            TimeRange::new(0, 50, true),
+            TimeRange::new(25, 100, true),
+            TimeRange::new(200, 300, false),
+        ];
+        let combined = charger_times_combine(1, &charger_times).unwrap();
+        assert_eq!(combined, vec![TimeRange::new(0, 100, true), TimeRange::new(200, 300, false)]);
+    }
+
+    #[test]
+    fn combine_keeps_end_of_enclosing_window() {
+        let charger_times = vec![TimeRange::new(0, 100, true), TimeRange::new(10, 20, true)];
+        let combined = charger_times_combine(1, &charger_times).unwrap();
+        assert_eq!(combined, vec![TimeRange::new(0, 100, true)]);
+    }
+
+    #[test]
+    fn combine_empty_reports() {
+        assert_eq!(charger_times_combine(1001, &[]).unwrap(), Vec::new());
+    }
+
+    #[test]
+    fn combine_conflicting_reports() {
+        let charger_times = vec![TimeRange::new(0, 50, true), TimeRange::new(25, 100, false)];
+        match charger_times_combine(1001, &charger_times) {
+            Err(UptimeError::ConflictingReports { charger_id, first, second }) => {
+                assert_eq!(charger_id, 1001);
+                assert_eq!(first, TimeRange::new(0, 50, true));
+                assert_eq!(second, TimeRange::new(25, 100, false));
+            },
+            other => panic!("Expected conflicting reports, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::availability::{apply_window, summarize_timeline, ComputeOptions, TimelineSummary};
+use crate::exclusion::ExclusionSet;
+use crate::metric::ChargerTimes;
+use crate::percent::UptimePercent;
+use crate::report::BucketUptime;
+use crate::time_range::TimeRange;
+use crate::window::{ReportingWindow, UnreportedTime};
+
+const NANOS_PER_HOUR: u64 = 60 * 60 * 1_000_000_000;
+const NANOS_PER_DAY: u64 = 24 * NANOS_PER_HOUR;
+const NANOS_PER_WEEK: u64 = 7 * NANOS_PER_DAY;
+
+/// The Unix epoch is a Thursday. Weeks start 4 days later, on Monday.
+const WEEK_OFFSET: u64 = 4 * NANOS_PER_DAY;
+
+/// Width of the buckets of an uptime series.
+///
+/// Buckets are aligned to the Unix epoch in UTC, so hourly buckets start on
+/// the hour and daily buckets start at midnight. Weekly buckets start on Monday.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BucketWidth {
+    Hour,
+    Day,
+    Week,
+    /// A custom width in nanoseconds, aligned to the Unix epoch.
+    Nanos(u64)
+}
+
+impl BucketWidth {
+
+    /// Width of a bucket in nanoseconds.
+    pub fn nanos(&self) -> u64 {
+        match self {
+            BucketWidth::Hour => NANOS_PER_HOUR,
+            BucketWidth::Day => NANOS_PER_DAY,
+            BucketWidth::Week => NANOS_PER_WEEK,
+            BucketWidth::Nanos(nanos) => *nanos,
+        }
+    }
+
+    /// Start of the bucket that contains `time`.
+    pub fn bucket_start(&self, time: u64) -> u64 {
+        let offset = if *self == BucketWidth::Week { WEEK_OFFSET } else { 0 };
+        if time < offset {
+            return 0;
+        }
+        (time - offset) / self.nanos() * self.nanos() + offset
+    }
+
+    /// End of the bucket that starts at `bucket_start`, capped to `u64::MAX`.
+    fn bucket_end(&self, bucket_start: u64) -> u64 {
+        // The first weekly bucket is shorter, and ends on the first Monday.
+        if *self == BucketWidth::Week && bucket_start < WEEK_OFFSET {
+            return WEEK_OFFSET;
+        }
+        bucket_start.saturating_add(self.nanos())
+    }
+}
+
+impl fmt::Display for BucketWidth {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BucketWidth::Hour => write!(f, "hour"),
+            BucketWidth::Day => write!(f, "day"),
+            BucketWidth::Week => write!(f, "week"),
+            BucketWidth::Nanos(nanos) => write!(f, "{}", nanos),
+        }
+    }
+}
+
+impl FromStr for BucketWidth {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<BucketWidth, String> {
+        match name {
+            "hour" => Ok(BucketWidth::Hour),
+            "day" => Ok(BucketWidth::Day),
+            "week" => Ok(BucketWidth::Week),
+            _ => match name.parse::<u64>() {
+                Ok(nanos) if nanos>0 => Ok(BucketWidth::Nanos(nanos)),
+                _ => Err(format!("Invalid bucket width '{}'. Expected one of: hour, day, week, or a width in nanoseconds.", name)),
+            },
+        }
+    }
+}
+
+/// Splits a station timeline into buckets, and computes the uptime of each.
+///
+/// Each bucket covers the part of the station's reported span inside it, so
+/// buckets add up to the station's overall `up_nanos` and `reported_nanos`.
+/// `TimeRange`s straddling a bucket boundary are split at the boundary.
+///
+/// ### Input:
+/// - `station_id`: ID of the station
+/// - `sorted_times`: `TimeRange`s of the station's chargers, sorted in ascending
+///   order of 'from time', and clipped to the reporting window, if any
+/// - `summary`: The station's overall summary, with the reporting window applied
+/// - `chargers`: Every charger at the station with its own `TimeRange`s, like
+///   `sorted_times`, for the station metric
+/// - `exclusions`: Excluded time of the station, already cut out of `sorted_times`
+/// - `options`: Options of the computation, for the reporting window, the
+///   station metric and the precision of percentages
+/// - `width`: Width of the buckets
+///
+/// ### Output:
+/// - `Vec<BucketUptime>`: Uptime of each bucket in ascending order. Buckets
+///   without reports are left out if unreported time is unknown, and count as
+///   down otherwise.
+pub(crate) fn station_buckets(station_id: u32, sorted_times: &[TimeRange], summary: &TimelineSummary, chargers: &[ChargerTimes],
+                              exclusions: &ExclusionSet, options: &ComputeOptions, width: BucketWidth) -> Vec<BucketUptime> {
+
+    let unreported = options.window.map_or(UnreportedTime::Down, |window| window.unreported);
+    let bucket_times = split_at_buckets(sorted_times, width);
+
+    let mut buckets: Vec<BucketUptime> = Vec::new();
+    let mut bucket_start = width.bucket_start(summary.first_reported_time);
+    while bucket_start < summary.last_reported_time {
+        let bucket_end = width.bucket_end(bucket_start);
+        // Part of the station's reported span inside this bucket
+        let bucket_window = ReportingWindow {
+            from: bucket_start.max(summary.first_reported_time),
+            to: bucket_end.min(summary.last_reported_time),
+            unreported,
+        };
+        let bucket_summary = bucket_times.get(&bucket_start)
+            .and_then(|times| summarize_timeline(times).map(|mut bucket_summary| {
+                bucket_summary.up_time = options.station_metric.up_time(chargers, bucket_start, bucket_end);
+                apply_window(bucket_summary, times, Some(&bucket_window))
+            }))
+            .map(|bucket_summary| exclusions.exclude_span(bucket_summary, Some(&bucket_window)));
+        let (up_nanos, reported_nanos, uptime_percent) = match bucket_summary {
+            Some(bucket_summary) => (bucket_summary.up_time, bucket_summary.reported_time, bucket_summary.uptime_percent(options.percent_format)),
+            None if unreported == UnreportedTime::Down => {
+                let reported_nanos = bucket_window.duration() - exclusions.excluded_within(bucket_window.from, bucket_window.to);
+                (0, reported_nanos, UptimePercent::new(0, 0, options.percent_format))
+            },
+            None => (0, 0, UptimePercent::new(0, 0, options.percent_format)),
+        };
+        if reported_nanos > 0 || bucket_summary.is_some() {
+            buckets.push(BucketUptime {
+                station_id,
+                bucket_start_nanos: bucket_start,
+                bucket_end_nanos: bucket_end,
+                uptime_percent,
+                up_nanos,
+                reported_nanos,
+            });
+        }
+        if bucket_end == u64::MAX {
+            break;
+        }
+        bucket_start = bucket_end;
+    }
+    buckets
+}
+
+/// Splits sorted windows at bucket boundaries.
+///
+/// ### Output:
+/// - `BTreeMap<u64, Vec<TimeRange>>`: The parts of the windows inside each
+///   bucket, by bucket start. Parts are in ascending order of 'from time'.
+fn split_at_buckets(sorted_times: &[TimeRange], width: BucketWidth) -> BTreeMap<u64, Vec<TimeRange>> {
+
+    let mut bucket_times: BTreeMap<u64, Vec<TimeRange>> = BTreeMap::new();
+    for time_range in sorted_times {
+        let mut from = time_range.from;
+        loop {
+            let bucket_start = width.bucket_start(from);
+            let to = time_range.to.min(width.bucket_end(bucket_start));
+            bucket_times.entry(bucket_start)
+                .or_default()
+                .push(TimeRange::new(from, to, time_range.up));
+            if to >= time_range.to {
+                break;
+            }
+            from = to;
+        }
+    }
+    bucket_times
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_width_names() {
+        assert_eq!("day".parse::<BucketWidth>(), Ok(BucketWidth::Day));
+        assert_eq!("250".parse::<BucketWidth>(), Ok(BucketWidth::Nanos(250)));
+        assert!("0".parse::<BucketWidth>().is_err());
+        assert!("fortnight".parse::<BucketWidth>().is_err());
+    }
+
+    #[test]
+    fn weeks_start_on_monday() {
+        // 1970-01-12 was a Monday
+        let monday = 11 * NANOS_PER_DAY;
+        assert_eq!(BucketWidth::Week.bucket_start(monday + NANOS_PER_HOUR), monday);
+        assert_eq!(BucketWidth::Week.bucket_start(monday - 1), monday - NANOS_PER_WEEK);
+        assert_eq!(BucketWidth::Week.bucket_start(NANOS_PER_DAY), 0);
+        assert_eq!(BucketWidth::Week.bucket_end(0), WEEK_OFFSET);
+    }
+
+    /// Every `TimeRange` as reported by a charger of its own.
+    fn one_charger_each(sorted_times: &[TimeRange]) -> Vec<ChargerTimes> {
+        sorted_times.iter()
+            .map(|time_range| ChargerTimes { power_kw: None, times: vec![time_range.clone()] })
+            .collect()
+    }
+
+    #[test]
+    fn split_ranges_straddling_boundaries() {
+        let sorted_times = vec![TimeRange::new(50, 250, true), TimeRange::new(120, 150, false)];
+        let summary = summarize_timeline(&sorted_times).unwrap();
+        let chargers = one_charger_each(&sorted_times);
+        let buckets = station_buckets(7, &sorted_times, &summary, &chargers, &ExclusionSet::default(), &ComputeOptions::default(), BucketWidth::Nanos(100));
+        let bucket_values: Vec<(u64, u64, u64, String)> = buckets.iter()
+            .map(|bucket| (bucket.bucket_start_nanos, bucket.up_nanos, bucket.reported_nanos, bucket.uptime_percent.to_string()))
+            .collect();
+        assert_eq!(bucket_values, vec![(0, 50, 50, "100".to_string()), (100, 100, 100, "100".to_string()), (200, 50, 50, "100".to_string())]);
+    }
+
+    #[test]
+    fn gaps_in_buckets_count_as_down() {
+        let sorted_times = vec![TimeRange::new(0, 50, true), TimeRange::new(350, 400, true)];
+        let summary = summarize_timeline(&sorted_times).unwrap();
+        let chargers = one_charger_each(&sorted_times);
+        let buckets = station_buckets(7, &sorted_times, &summary, &chargers, &ExclusionSet::default(), &ComputeOptions::default(), BucketWidth::Nanos(100));
+        let bucket_values: Vec<(u64, u64, u64)> = buckets.iter()
+            .map(|bucket| (bucket.bucket_start_nanos, bucket.up_nanos, bucket.reported_nanos))
+            .collect();
+        assert_eq!(bucket_values, vec![(0, 50, 100), (100, 0, 100), (200, 0, 100), (300, 50, 100)]);
+
+        let options = ComputeOptions {
+            window: Some(ReportingWindow::new(0, 400, UnreportedTime::Unknown).unwrap()),
+            ..ComputeOptions::default()
+        };
+        let buckets = station_buckets(7, &sorted_times, &summary, &chargers, &ExclusionSet::default(), &options, BucketWidth::Nanos(100));
+        let bucket_values: Vec<(u64, u64, u64)> = buckets.iter()
+            .map(|bucket| (bucket.bucket_start_nanos, bucket.up_nanos, bucket.reported_nanos))
+            .collect();
+        assert_eq!(bucket_values, vec![(0, 50, 50), (300, 50, 50)]);
+    }
+}
@@ -0,0 +1,116 @@
+use std::fmt;
+use std::io::{BufRead, BufReader, Error};
+use std::path::Path;
+
+use flate2::bufread::MultiGzDecoder;
+
+/// First bytes of a gzip stream.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// First bytes of a zstd frame.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// Compression of an input stream.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd
+}
+
+impl Compression {
+
+    /// Detects the compression of an input from its first bytes. If they
+    /// don't match a known format, falls back to the file extension (`.gz`,
+    /// `.zst`), so a corrupted archive fails to decompress instead of being
+    /// read as text.
+    /// ### Input:
+    /// - `header`: The first bytes of the input. Fewer than 4 bytes is fine
+    /// - `file_path`: Path of the input, used for its extension
+    ///
+    /// ### Output:
+    /// - `Compression`: The detected compression.
+    pub fn detect(header: &[u8], file_path: &str) -> Compression {
+        if header.starts_with(&GZIP_MAGIC) {
+            return Compression::Gzip;
+        }
+        if header.starts_with(&ZSTD_MAGIC) {
+            return Compression::Zstd;
+        }
+        match Path::new(file_path).extension().and_then(|extension| extension.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Name of the compression format.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Compression::None => "none",
+            Compression::Gzip => "gzip",
+            Compression::Zstd => "zstd",
+        }
+    }
+}
+
+impl fmt::Display for Compression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Wraps a reader in a streaming decoder if its input is compressed. The
+/// input is sniffed without consuming it, so uncompressed input is read as is.
+/// ### Input:
+/// - `reader`: A buffered reader over the raw input
+/// - `file_path`: Path of the input, used for its extension
+///
+/// ### Output:
+/// - `Result<Box<dyn BufRead>, Error>`: A buffered reader over the decompressed
+///   input wrapped in `Ok()` if successful and `Error` in case of error.
+pub fn decompress(mut reader: Box<dyn BufRead>, file_path: &str) -> Result<Box<dyn BufRead>, Error> {
+
+    let compression = Compression::detect(reader.fill_buf()?, file_path);
+    match compression {
+        Compression::None => Ok(reader),
+        Compression::Gzip => Ok(Box::new(BufReader::new(MultiGzDecoder::new(reader)))),
+        Compression::Zstd => Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(reader)?))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    const INPUT: &str = "[Stations]\n0 1001\n\n[Charger Availability Reports]\n1001 0 100 true\n";
+
+    fn read_all(raw: Vec<u8>, file_path: &str) -> String {
+        let mut output = String::new();
+        decompress(Box::new(std::io::Cursor::new(raw)), file_path).unwrap()
+            .read_to_string(&mut output).unwrap();
+        output
+    }
+
+    #[test]
+    fn detects_by_magic_bytes_then_extension() {
+        assert_eq!(Compression::detect(&[0x1f, 0x8b, 0x08], "reports.txt"), Compression::Gzip);
+        assert_eq!(Compression::detect(&ZSTD_MAGIC, "-"), Compression::Zstd);
+        assert_eq!(Compression::detect(b"[Stations]", "reports.txt.zst"), Compression::Zstd);
+        assert_eq!(Compression::detect(b"[Stations]", "reports.txt"), Compression::None);
+        assert_eq!(Compression::detect(b"", "-"), Compression::None);
+    }
+
+    #[test]
+    fn decompresses_gzip_and_zstd() {
+        let mut gzip_encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip_encoder.write_all(INPUT.as_bytes()).unwrap();
+        assert_eq!(read_all(gzip_encoder.finish().unwrap(), "-"), INPUT);
+
+        let zstd_input = zstd::encode_all(INPUT.as_bytes(), 0).unwrap();
+        assert_eq!(read_all(zstd_input, "reports"), INPUT);
+
+        assert_eq!(read_all(INPUT.as_bytes().to_vec(), "reports.txt"), INPUT);
+    }
+}
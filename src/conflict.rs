@@ -0,0 +1,242 @@
+use std::collections::BTreeSet;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::availability::charger_times_combine;
+use crate::error::UptimeError;
+use crate::time_range::TimeRange;
+
+/// How to handle overlapping reports of the same charger that disagree on
+/// `up` status.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// Fail with `UptimeError::ConflictingReports`.
+    #[default]
+    Fail,
+    /// The overlap counts as down.
+    DownWins,
+    /// The overlap counts as up.
+    UpWins,
+    /// The overlap takes the status of the report that appears last in the input.
+    LatestLineWins,
+    /// The overlap is dropped from the charger's reports. Like any other gap
+    /// in reported time, it doesn't count as uptime for the charger.
+    SplitAsUnknown
+}
+
+impl ConflictPolicy {
+
+    /// Name of the policy, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Fail => "fail",
+            ConflictPolicy::DownWins => "down-wins",
+            ConflictPolicy::UpWins => "up-wins",
+            ConflictPolicy::LatestLineWins => "latest-line-wins",
+            ConflictPolicy::SplitAsUnknown => "split-overlap-as-unknown",
+        }
+    }
+}
+
+impl fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ConflictPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<ConflictPolicy, String> {
+        match name {
+            "fail" => Ok(ConflictPolicy::Fail),
+            "down-wins" => Ok(ConflictPolicy::DownWins),
+            "up-wins" => Ok(ConflictPolicy::UpWins),
+            "latest-line-wins" => Ok(ConflictPolicy::LatestLineWins),
+            "split-overlap-as-unknown" => Ok(ConflictPolicy::SplitAsUnknown),
+            _ => Err(format!("Unknown conflict policy '{}'. Expected one of: fail, down-wins, up-wins, latest-line-wins, split-overlap-as-unknown.", name)),
+        }
+    }
+}
+
+/// Status of a charger between two consecutive report boundaries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum SegmentStatus {
+    Up,
+    Down,
+    Unknown
+}
+
+/// Combines a charger's reports into sorted, non-overlapping windows,
+/// resolving overlaps that disagree on `up` status with `policy`.
+///
+/// ### Input:
+/// - `charger_id`: ID of the charger, used for error reporting
+/// - `charger_times`: `TimeRange`s of a single charger, in input order
+/// - `policy`: How to resolve conflicting overlaps
+///
+/// ### Output:
+/// - `Result<(Vec<TimeRange>, usize), UptimeError>`: The combined windows and the
+///   number of conflicting overlaps that were resolved, wrapped in `Ok()` if
+///   successful. `UptimeError::ConflictingReports` with `ConflictPolicy::Fail`
+///   if there is a conflict.
+pub fn combine_with_policy(charger_id: u32, charger_times: &[TimeRange], policy: ConflictPolicy)
+                           -> Result<(Vec<TimeRange>, usize), UptimeError> {
+
+    if policy == ConflictPolicy::Fail {
+        let mut charger_times_sorted: Vec<TimeRange> = charger_times.to_vec();
+        charger_times_sorted.sort();
+        return Ok((charger_times_combine(charger_id, &charger_times_sorted)?, 0));
+    }
+
+    // Zero length reports don't have a status over any time, but still mark
+    // the charger as reporting at that instant. Passing them through as is.
+    let mut combined: Vec<TimeRange> = charger_times.iter()
+        .filter(|charger_time| charger_time.from == charger_time.to)
+        .cloned()
+        .collect();
+
+    // Boundaries of each report, as (time, is start, index in input order).
+    // Ends sort before starts at the same time, since windows are [from, to).
+    let mut boundaries: Vec<(u64, bool, usize)> = Vec::new();
+    for (index, charger_time) in charger_times.iter().enumerate() {
+        if charger_time.from < charger_time.to {
+            boundaries.push((charger_time.from, true, index));
+            boundaries.push((charger_time.to, false, index));
+        }
+    }
+    boundaries.sort();
+
+    let mut active: BTreeSet<usize> = BTreeSet::new();
+    let mut active_up: usize = 0;
+    let mut conflicts: usize = 0;
+    let mut in_conflict = false;
+    let mut open_segment: Option<(u64, SegmentStatus)> = None;
+    let mut i = 0;
+    while i < boundaries.len() {
+        let time = boundaries[i].0;
+        while i < boundaries.len() && boundaries[i].0 == time {
+            let (_, is_start, index) = boundaries[i];
+            if is_start {
+                active.insert(index);
+                if charger_times[index].up { active_up += 1; }
+            } else {
+                active.remove(&index);
+                if charger_times[index].up { active_up -= 1; }
+            }
+            i += 1;
+        }
+
+        // Status from `time` up to the next boundary
+        let conflicting = active_up > 0 && active_up < active.len();
+        let status = if active.is_empty() {
+            None
+        } else if !conflicting {
+            Some(if active_up > 0 { SegmentStatus::Up } else { SegmentStatus::Down })
+        } else {
+            Some(match policy {
+                ConflictPolicy::DownWins => SegmentStatus::Down,
+                ConflictPolicy::UpWins => SegmentStatus::Up,
+                ConflictPolicy::LatestLineWins => {
+                    let latest = *active.iter().next_back().unwrap();
+                    if charger_times[latest].up { SegmentStatus::Up } else { SegmentStatus::Down }
+                },
+                ConflictPolicy::SplitAsUnknown | ConflictPolicy::Fail => SegmentStatus::Unknown,
+            })
+        };
+        if conflicting && !in_conflict {
+            conflicts += 1;
+        }
+        in_conflict = conflicting;
+
+        // Closing the open segment if the status changes here
+        if let Some((segment_from, segment_status)) = open_segment {
+            if status == Some(segment_status) {
+                continue;
+            }
+            if segment_status != SegmentStatus::Unknown {
+                combined.push(TimeRange::new(segment_from, time, segment_status == SegmentStatus::Up));
+            }
+        }
+        open_segment = status.map(|segment_status| (time, segment_status));
+    }
+
+    combined.sort();
+    Ok((combined, conflicts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conflicting_reports() -> Vec<TimeRange> {
+        vec![
+            TimeRange::new(50, 150, false),
+            TimeRange::new(0, 100, true),
+            TimeRange::new(200, 300, true),
+        ]
+    }
+
+    #[test]
+    fn fail_policy_returns_error() {
+        let combine_result = combine_with_policy(1, &conflicting_reports(), ConflictPolicy::Fail);
+        assert!(matches!(combine_result, Err(UptimeError::ConflictingReports { charger_id: 1, .. })));
+    }
+
+    #[test]
+    fn down_wins_policy() {
+        let (combined, conflicts) = combine_with_policy(1, &conflicting_reports(), ConflictPolicy::DownWins).unwrap();
+        assert_eq!(conflicts, 1);
+        assert_eq!(combined, vec![
+            TimeRange::new(0, 50, true),
+            TimeRange::new(50, 150, false),
+            TimeRange::new(200, 300, true),
+        ]);
+    }
+
+    #[test]
+    fn up_wins_policy() {
+        let (combined, conflicts) = combine_with_policy(1, &conflicting_reports(), ConflictPolicy::UpWins).unwrap();
+        assert_eq!(conflicts, 1);
+        assert_eq!(combined, vec![
+            TimeRange::new(0, 100, true),
+            TimeRange::new(100, 150, false),
+            TimeRange::new(200, 300, true),
+        ]);
+    }
+
+    #[test]
+    fn latest_line_wins_policy() {
+        // The up report comes after the down report in the input
+        let (combined, _) = combine_with_policy(1, &conflicting_reports(), ConflictPolicy::LatestLineWins).unwrap();
+        assert_eq!(combined[0], TimeRange::new(0, 100, true));
+        assert_eq!(combined[1], TimeRange::new(100, 150, false));
+    }
+
+    #[test]
+    fn unknown_policy_drops_overlap() {
+        let (combined, conflicts) = combine_with_policy(1, &conflicting_reports(), ConflictPolicy::SplitAsUnknown).unwrap();
+        assert_eq!(conflicts, 1);
+        assert_eq!(combined, vec![
+            TimeRange::new(0, 50, true),
+            TimeRange::new(100, 150, false),
+            TimeRange::new(200, 300, true),
+        ]);
+    }
+
+    #[test]
+    fn no_conflicts_keeps_zero_length_reports() {
+        let charger_times = vec![TimeRange::new(2, 2, true), TimeRange::new(10, 20, false), TimeRange::new(15, 30, false)];
+        let (combined, conflicts) = combine_with_policy(1, &charger_times, ConflictPolicy::DownWins).unwrap();
+        assert_eq!(conflicts, 0);
+        assert_eq!(combined, vec![TimeRange::new(2, 2, true), TimeRange::new(10, 30, false)]);
+    }
+
+    #[test]
+    fn policy_names_round_trip() {
+        for policy in [ConflictPolicy::Fail, ConflictPolicy::DownWins, ConflictPolicy::UpWins, ConflictPolicy::LatestLineWins, ConflictPolicy::SplitAsUnknown] {
+            assert_eq!(policy.name().parse::<ConflictPolicy>(), Ok(policy));
+        }
+        assert!("sometimes".parse::<ConflictPolicy>().is_err());
+    }
+}
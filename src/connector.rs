@@ -0,0 +1,243 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::availability::{summarize_timeline, ComputeOptions};
+use crate::conflict::combine_with_policy;
+use crate::error::UptimeError;
+use crate::exclusion::{ExclusionIndex, StationExclusions};
+use crate::fleet::Fleet;
+use crate::report::ConnectorUptime;
+use crate::time_range::TimeRange;
+
+/// How the up status of a charger's connectors rolls up to the charger.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConnectorRollup {
+    /// The charger is up while any of its connectors is up.
+    #[default]
+    AnyUp,
+    /// The charger is up only while all of its connectors are up.
+    AllUp
+}
+
+impl ConnectorRollup {
+
+    /// Name of the roll-up, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConnectorRollup::AnyUp => "any-up",
+            ConnectorRollup::AllUp => "all-up",
+        }
+    }
+}
+
+impl fmt::Display for ConnectorRollup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ConnectorRollup {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<ConnectorRollup, String> {
+        match name {
+            "any-up" => Ok(ConnectorRollup::AnyUp),
+            "all-up" => Ok(ConnectorRollup::AllUp),
+            _ => Err(format!("Unknown connector roll-up '{}'. Expected one of: any-up, all-up.", name)),
+        }
+    }
+}
+
+/// Connector reports of a fleet, rolled up to its chargers.
+#[derive(Debug, Default)]
+pub(crate) struct RolledUpConnectors {
+    /// Reports of each charger, including the windows rolled up from its
+    /// connectors.
+    pub charger_uptime_map: HashMap<u32, Vec<TimeRange>>,
+    /// Uptime of each connector of a charger that belongs to a station.
+    pub connectors: Vec<ConnectorUptime>,
+    /// Number of conflicting overlaps resolved in connector reports, for each
+    /// Charger ID that had any.
+    pub conflicts_resolved: BTreeMap<u32, usize>
+}
+
+/// Rolls the connector reports of a fleet up to their chargers, and computes
+/// the uptime of each connector.
+///
+/// Each connector's reports are combined with the conflict policy on their own,
+/// then rolled up as set in `options.connector_rollup`. The rolled up windows
+/// are added to the charger's own reports, if it has any.
+///
+/// ### Input:
+/// - `fleet`: The fleet, with at least one connector report
+/// - `options`: Options of the computation
+///
+/// ### Output:
+/// - `Result<RolledUpConnectors, UptimeError>`: The rolled up reports wrapped in
+///   `Ok()` if successful and `UptimeError` in case of conflicting connector
+///   reports that the conflict policy doesn't resolve, for the lowest Charger ID
+///   with any.
+pub(crate) fn roll_up_connectors(fleet: &Fleet, options: &ComputeOptions) -> Result<RolledUpConnectors, UptimeError> {
+
+    let mut rolled_up = RolledUpConnectors {
+        charger_uptime_map: fleet.charger_uptime_map().clone(),
+        ..RolledUpConnectors::default()
+    };
+    let exclusion_index = ExclusionIndex::new(fleet.exclusions());
+    let mut station_exclusions: HashMap<u32, StationExclusions> = HashMap::new();
+    let mut charger_ids: Vec<u32> = fleet.connector_uptime_map().keys().copied().collect();
+    charger_ids.sort();
+    for charger_id in charger_ids {
+        let connector_reports = &fleet.connector_uptime_map()[&charger_id];
+        // Chargers without configured connectors roll up every connector that reported
+        let connector_ids: Vec<u32> = match fleet.connectors().get(&charger_id) {
+            Some(configured) => configured.iter().copied().collect(),
+            None => connector_reports.keys().copied().collect(),
+        };
+        let mut connector_times: Vec<(u32, Vec<TimeRange>)> = Vec::new();
+        for connector_id in &connector_ids {
+            let Some(reports) = connector_reports.get(connector_id) else {
+                continue;
+            };
+            let reports = options.up_statuses.apply_all(reports);
+            let (combined, conflicts) = combine_with_policy(charger_id, &reports, options.conflict_policy)?;
+            if conflicts>0 {
+                *rolled_up.conflicts_resolved.entry(charger_id).or_default() += conflicts;
+            }
+            connector_times.push((*connector_id, combined));
+        }
+        let charger_times: Vec<&[TimeRange]> = connector_times.iter().map(|(_, times)| times.as_slice()).collect();
+        rolled_up.charger_uptime_map.entry(charger_id)
+            .or_default()
+            .extend(roll_up(&charger_times, connector_ids.len(), options.connector_rollup));
+
+        let Some(station_id) = fleet.station_of(charger_id) else {
+            continue;
+        };
+        let exclusions = station_exclusions.entry(station_id)
+            .or_insert_with(|| exclusion_index.station(station_id, &fleet.station_charger_map()[&station_id]))
+            .charger(charger_id);
+        for (connector_id, mut times) in connector_times {
+            if let Some(window) = &options.window {
+                times = window.clip(&times);
+            }
+            let Some(summary) = summarize_timeline(&times) else {
+                continue;
+            };
+            let kept_times = exclusions.remove_from(times);
+            let summary = exclusions.exclude(summary, &kept_times, options.window.as_ref());
+            rolled_up.connectors.push(ConnectorUptime {
+                charger_id,
+                connector_id,
+                station_id,
+                uptime_percent: summary.uptime_percent(options.percent_format),
+                reported_nanos: summary.reported_time,
+                up_nanos: summary.up_time,
+                excluded_nanos: summary.excluded_time,
+            });
+        }
+    }
+    Ok(rolled_up)
+}
+
+/// Rolls the combined reports of a charger's connectors up to windows of the
+/// charger.
+///
+/// ### Input:
+/// - `connector_times`: Sorted, non-overlapping `TimeRange`s of each connector
+///   that reported
+/// - `connector_count`: Number of connectors of the charger, including ones
+///   without reports
+/// - `rollup`: How connector up status rolls up
+///
+/// ### Output:
+/// - `Vec<TimeRange>`: Sorted, non-overlapping windows covering the time any
+///   connector reported, up as set in `rollup`. With `ConnectorRollup::AllUp`,
+///   a connector without a report at a time is not up then. Zero length
+///   reports are left out.
+pub(crate) fn roll_up(connector_times: &[&[TimeRange]], connector_count: usize, rollup: ConnectorRollup) -> Vec<TimeRange> {
+
+    // Changes in the number of connectors reporting, and reporting up
+    let mut changes: Vec<(u64, isize, isize)> = Vec::new();
+    for time_range in connector_times.iter().copied().flatten().filter(|time_range| time_range.from < time_range.to) {
+        let up = isize::from(time_range.up);
+        changes.push((time_range.from, 1, up));
+        changes.push((time_range.to, -1, -up));
+    }
+    changes.sort_by_key(|(time, _, _)| *time);
+
+    let mut rolled_up: Vec<TimeRange> = Vec::new();
+    let (mut reporting, mut reporting_up) = (0isize, 0isize);
+    let mut previous_time = 0;
+    for (time, reporting_change, up_change) in changes {
+        if time > previous_time && reporting > 0 {
+            let up = match rollup {
+                ConnectorRollup::AnyUp => reporting_up > 0,
+                ConnectorRollup::AllUp => reporting_up as usize == connector_count,
+            };
+            match rolled_up.last_mut() {
+                Some(last) if last.to == previous_time && last.up == up => last.to = time,
+                _ => rolled_up.push(TimeRange::new(previous_time, time, up)),
+            }
+        }
+        reporting += reporting_change;
+        reporting_up += up_change;
+        previous_time = time;
+    }
+    rolled_up
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictPolicy;
+
+    #[test]
+    fn roll_up_connectors_any_and_all() {
+        let ccs = vec![TimeRange::new(0, 50, true), TimeRange::new(50, 100, false)];
+        let chademo = vec![TimeRange::new(20, 80, true), TimeRange::new(80, 80, false)];
+        let connector_times = [ccs.as_slice(), chademo.as_slice()];
+        assert_eq!(roll_up(&connector_times, 2, ConnectorRollup::AnyUp), vec![
+            TimeRange::new(0, 80, true),
+            TimeRange::new(80, 100, false),
+        ]);
+        assert_eq!(roll_up(&connector_times, 2, ConnectorRollup::AllUp), vec![
+            TimeRange::new(0, 20, false),
+            TimeRange::new(20, 50, true),
+            TimeRange::new(50, 100, false),
+        ]);
+        // A third connector that never reported is never up
+        assert_eq!(roll_up(&connector_times, 3, ConnectorRollup::AllUp), vec![TimeRange::new(0, 100, false)]);
+        assert_eq!("all-up".parse::<ConnectorRollup>(), Ok(ConnectorRollup::AllUp));
+    }
+
+    #[test]
+    fn connectors_roll_up_to_stations() {
+        let input = "[Stations]\n0 1001:1,2 1002\n\n[Charger Availability Reports]\n\
+            1001:1 0 100 true\n1001:2 0 50 false\n1001:2 50 100 true\n1001:3 0 100 false\n1002 100 200 false\n";
+        let fleet = Fleet::from_reader(input.as_bytes()).unwrap();
+        let report = fleet.compute().unwrap();
+        assert_eq!(report.to_string(), "0 50");
+        assert_eq!(report.connector_lines(), "1001:1 0 100 100 100\n1001:2 0 50 100 50");
+
+        let options = ComputeOptions { connector_rollup: ConnectorRollup::AllUp, ..ComputeOptions::default() };
+        let report = fleet.compute_with_options(&options).unwrap();
+        assert_eq!(report.charger_lines(), "1001 0 50 100 50\n1002 0 0 100 0");
+    }
+
+    #[test]
+    fn charger_and_connector_reports_combine() {
+        let input = "[Stations]\n0 1001:1,2\n\n[Charger Availability Reports]\n\
+            1001:1 0 100 true\n1001:2 0 100 false\n1001 100 200 false\n";
+        let fleet = Fleet::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(fleet.compute().unwrap().charger_lines(), "1001 0 50 200 100");
+
+        // The charger's own reports can conflict with the rolled up windows
+        let conflicting = format!("{}1001 50 150 false\n", input);
+        let fleet = Fleet::from_reader(conflicting.as_bytes()).unwrap();
+        assert!(matches!(fleet.compute(), Err(UptimeError::ConflictingReports { charger_id: 1001, .. })));
+        let options = ComputeOptions { conflict_policy: ConflictPolicy::DownWins, ..ComputeOptions::default() };
+        assert_eq!(fleet.compute_with_options(&options).unwrap().charger_lines(), "1001 0 25 200 50");
+    }
+}
@@ -0,0 +1,298 @@
+use std::io::Read;
+use std::ops::Range;
+
+use csv::{ReaderBuilder, StringRecord, Trim};
+
+use crate::error::{Location, ParseErrorKind, UptimeError};
+use crate::fleet::Fleet;
+use crate::parser::{report_time_range, LineErrors, ReadOptions};
+
+/// Options for reading station mappings and availability reports from CSV.
+#[derive(Clone, Debug)]
+pub struct CsvOptions {
+    /// Field delimiter. Defaults to `,`.
+    pub delimiter: u8,
+    /// Header names of the columns to read.
+    pub columns: CsvColumns
+}
+
+/// Header names of the CSV columns to read.
+///
+/// The station mapping CSV has one `station_id`, `charger_id` pair per record.
+/// An empty `charger_id` adds a station without chargers. The availability
+/// report CSV has `charger_id`, `start_ns`, `end_ns`, and `up` columns.
+/// Other columns are ignored.
+#[derive(Clone, Debug)]
+pub struct CsvColumns {
+    pub station_id: String,
+    pub charger_id: String,
+    pub start_ns: String,
+    pub end_ns: String,
+    pub up: String
+}
+
+impl Default for CsvOptions {
+    fn default() -> CsvOptions {
+        CsvOptions {
+            delimiter: b',',
+            columns: CsvColumns::default(),
+        }
+    }
+}
+
+impl Default for CsvColumns {
+    fn default() -> CsvColumns {
+        CsvColumns {
+            station_id: "station_id".to_string(),
+            charger_id: "charger_id".to_string(),
+            start_ns: "start_ns".to_string(),
+            end_ns: "end_ns".to_string(),
+            up: "up".to_string(),
+        }
+    }
+}
+
+impl CsvColumns {
+
+    /// Sets the header name of a column from a `<column>=<header name>` mapping,
+    /// such as `start_ns=begin`.
+    /// ### Output:
+    /// - `Result<(), String>`: An error message if the mapping is invalid.
+    pub fn set_from_mapping(&mut self, mapping: &str) -> Result<(), String> {
+        let (column, header) = match mapping.split_once('=') {
+            Some((column, header)) if !header.is_empty() => (column, header.to_string()),
+            _ => return Err(format!("Invalid CSV column mapping '{}'. Expected <column>=<header name>.", mapping)),
+        };
+        match column {
+            "station_id" => self.station_id = header,
+            "charger_id" => self.charger_id = header,
+            "start_ns" => self.start_ns = header,
+            "end_ns" => self.end_ns = header,
+            "up" => self.up = header,
+            _ => return Err(format!("Unknown CSV column '{}'. Expected one of: station_id, charger_id, start_ns, end_ns, up.", column)),
+        }
+        Ok(())
+    }
+}
+
+/// A CSV record with its line number, for locating errors.
+struct CsvRecord<'a> {
+    record: &'a StringRecord,
+    line: usize,
+    delimiter: char
+}
+
+impl CsvRecord<'_> {
+
+    fn field(&self, index: usize) -> &str {
+        self.record.get(index).unwrap_or("")
+    }
+
+    /// The record as a single line, with fields joined by the delimiter.
+    fn source_line(&self) -> String {
+        let fields: Vec<&str> = self.record.iter().collect();
+        fields.join(&self.delimiter.to_string())
+    }
+
+    /// Location of a field within `source_line`.
+    fn locate(&self, index: usize) -> Box<Location> {
+        let source_line = self.source_line();
+        let start: usize = self.record.iter()
+            .take(index)
+            .map(|field| field.len() + self.delimiter.len_utf8())
+            .sum();
+        let span: Range<usize> = start..start + self.field(index).len();
+        Box::new(Location::new(self.line, &source_line, span))
+    }
+
+    fn error(&self, index: usize, kind: ParseErrorKind) -> UptimeError {
+        UptimeError::Parse { location: self.locate(index), kind }
+    }
+}
+
+/// Reads station mappings from CSV into a `Fleet`, with the same validation
+/// as the `[Stations]` section.
+/// ### Input:
+/// - `fleet`: The fleet to add stations to
+/// - `reader`: A reader over CSV with a header record
+/// - `csv_options`: Delimiter and header names
+/// - `read_options`: Options for handling invalid records
+///
+/// ### Output:
+/// - `Result<(), UptimeError>`: `UptimeError` in case of error.
+pub fn read_stations_csv<R: Read>(fleet: &mut Fleet, reader: R, csv_options: &CsvOptions, read_options: &ReadOptions) -> Result<(), UptimeError> {
+
+    let columns = &csv_options.columns;
+    read_csv(fleet, reader, csv_options, read_options, &[&columns.station_id, &columns.charger_id],
+        |fleet, record, indexes| {
+            let station_id_str = record.field(indexes[0]);
+            let station_id = station_id_str.parse::<u32>()
+                .map_err(|_| record.error(indexes[0], ParseErrorKind::InvalidStationId { station_id: station_id_str.to_string() }))?;
+            let charger_id_str = record.field(indexes[1]);
+            let mut chargers: Vec<u32> = Vec::new();
+            if !charger_id_str.is_empty() {
+                let charger_id = charger_id_str.parse::<u32>()
+                    .map_err(|_| record.error(indexes[1], ParseErrorKind::InvalidStationChargerId { station_id, charger_id: charger_id_str.to_string() }))?;
+                chargers.push(charger_id);
+            }
+            fleet.add_station(station_id, &chargers).map_err(|mut station_error| {
+                if let UptimeError::ChargerMappedToMultipleStations { location, .. } = &mut station_error {
+                    *location = Some(record.locate(indexes[1]));
+                }
+                station_error
+            })
+        })
+}
+
+/// Reads charger availability reports from CSV into a `Fleet`, with the same
+/// validation as the `[Charger Availability Reports]` section.
+/// ### Input:
+/// - `fleet`: The fleet to add reports to
+/// - `reader`: A reader over CSV with a header record
+/// - `csv_options`: Delimiter and header names
+/// - `read_options`: Options for handling invalid records
+///
+/// ### Output:
+/// - `Result<(), UptimeError>`: `UptimeError` in case of error.
+pub fn read_reports_csv<R: Read>(fleet: &mut Fleet, reader: R, csv_options: &CsvOptions, read_options: &ReadOptions) -> Result<(), UptimeError> {
+
+    let columns = &csv_options.columns;
+    read_csv(fleet, reader, csv_options, read_options, &[&columns.charger_id, &columns.start_ns, &columns.end_ns, &columns.up],
+        |fleet, record, indexes| {
+            let charger_id_str = record.field(indexes[0]);
+            let charger_id = charger_id_str.parse::<u32>()
+                .map_err(|_| record.error(indexes[0], ParseErrorKind::InvalidAvailabilityChargerId { charger_id: charger_id_str.to_string() }))?;
+            let start_time_str = record.field(indexes[1]);
+            let from = start_time_str.parse::<u64>()
+                .map_err(|_| record.error(indexes[1], ParseErrorKind::InvalidStartTime { charger_id, start_time: start_time_str.to_string() }))?;
+            let end_time_str = record.field(indexes[2]);
+            let to = end_time_str.parse::<u64>()
+                .map_err(|_| record.error(indexes[2], ParseErrorKind::InvalidEndTime { charger_id, end_time: end_time_str.to_string() }))?;
+            let up_status = record.field(indexes[3]);
+            let time_range = report_time_range(from, to, up_status)
+                .ok_or_else(|| record.error(indexes[3], ParseErrorKind::InvalidUpStatus {
+                    charger_id,
+                    up_status: up_status.to_string(),
+                    entry: record.source_line(),
+                }))?;
+            if from>to {
+                return Err(record.error(indexes[1], ParseErrorKind::InvertedTimeRange { charger_id, from, to }));
+            }
+            fleet.add_report(charger_id, time_range);
+            Ok(())
+        })
+}
+
+/// Reads CSV records, finding the index of each of `column_names` in the
+/// header, and passes each record to `read_record`.
+fn read_csv<R, F>(fleet: &mut Fleet, reader: R, csv_options: &CsvOptions, read_options: &ReadOptions,
+                  column_names: &[&String], mut read_record: F) -> Result<(), UptimeError>
+    where R: Read,
+          F: FnMut(&mut Fleet, &CsvRecord, &[usize]) -> Result<(), UptimeError> {
+
+    let mut csv_reader = ReaderBuilder::new()
+        .delimiter(csv_options.delimiter)
+        .has_headers(true)
+        .trim(Trim::All)
+        .from_reader(reader);
+    let delimiter = csv_options.delimiter as char;
+
+    let headers = csv_reader.headers().map_err(csv_error)?.clone();
+    let mut indexes: Vec<usize> = Vec::new();
+    for column_name in column_names {
+        match headers.iter().position(|header| header == column_name.as_str()) {
+            Some(index) => indexes.push(index),
+            None => {
+                let header_record = CsvRecord { record: &headers, line: 1, delimiter };
+                let source_line = header_record.source_line();
+                return Err(UptimeError::Parse {
+                    location: Box::new(Location::new(1, &source_line, 0..source_line.len())),
+                    kind: ParseErrorKind::MissingCsvColumn { column: column_name.to_string() },
+                });
+            },
+        }
+    }
+
+    let mut line_errors = LineErrors::new(read_options);
+    let mut record = StringRecord::new();
+    loop {
+        match csv_reader.read_record(&mut record) {
+            Ok(false) => break,
+            Ok(true) => {
+                let line = record.position().map_or(0, |position| position.line() as usize);
+                let csv_record = CsvRecord { record: &record, line, delimiter };
+                if let Err(record_error) = read_record(fleet, &csv_record, &indexes) {
+                    line_errors.handle(fleet, record_error)?;
+                }
+            },
+            Err(read_error) => {
+                let record_error = csv_error(read_error);
+                if let UptimeError::Io(_) = record_error {
+                    return Err(record_error);
+                }
+                line_errors.handle(fleet, record_error)?;
+            },
+        }
+    }
+    line_errors.finish()
+}
+
+/// Converts a `csv::Error` into an `UptimeError`, keeping the line number
+/// of the record it was found in.
+fn csv_error(read_error: csv::Error) -> UptimeError {
+    let line = read_error.position().map_or(0, |position| position.line() as usize);
+    let message = read_error.to_string();
+    match read_error.into_kind() {
+        csv::ErrorKind::Io(io_error) => UptimeError::Io(io_error),
+        _ => UptimeError::Parse {
+            location: Box::new(Location::new(line, "", 0..0)),
+            kind: ParseErrorKind::MalformedCsvRecord { message },
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time_range::TimeRange;
+
+    #[test]
+    fn read_csv_into_fleet() {
+        let stations = "station_id,charger_id\n0,1001\n0,1002\n1,1003\n2,\n";
+        let reports = "charger_id,start_ns,end_ns,up\n1001,0,50000,true\n1002,50000,100000,True\n1003,25000,75000,false\n";
+        let mut fleet = Fleet::new();
+        read_stations_csv(&mut fleet, stations.as_bytes(), &CsvOptions::default(), &ReadOptions::default()).unwrap();
+        read_reports_csv(&mut fleet, reports.as_bytes(), &CsvOptions::default(), &ReadOptions::default()).unwrap();
+        assert_eq!(fleet.station_charger_map()[&2].len(), 0);
+        assert_eq!(fleet.compute().unwrap().to_string(), "0 100\n1 0");
+    }
+
+    #[test]
+    fn read_csv_with_delimiter_and_column_mapping() {
+        let reports = "begin;end;charger;status;note\n0;100;\"7\";true;\"a; b\"\n";
+        let mut csv_options = CsvOptions { delimiter: b';', ..CsvOptions::default() };
+        csv_options.columns.set_from_mapping("charger_id=charger").unwrap();
+        csv_options.columns.set_from_mapping("start_ns=begin").unwrap();
+        csv_options.columns.set_from_mapping("end_ns=end").unwrap();
+        csv_options.columns.set_from_mapping("up=status").unwrap();
+        let mut fleet = Fleet::new();
+        read_reports_csv(&mut fleet, reports.as_bytes(), &csv_options, &ReadOptions::default()).unwrap();
+        assert_eq!(fleet.charger_uptime_map()[&7], vec![TimeRange::new(0, 100, true)]);
+        assert!(csv_options.columns.set_from_mapping("voltage=v").is_err());
+    }
+
+    #[test]
+    fn read_csv_errors_are_located() {
+        let reports = "charger_id,start_ns,end_ns,up\n1001,0,50,true\n1001,100,50,true\n";
+        let mut fleet = Fleet::new();
+        let read_result = read_reports_csv(&mut fleet, reports.as_bytes(), &CsvOptions::default(), &ReadOptions::default());
+        let read_error = read_result.unwrap_err();
+        assert_eq!(read_error.to_string(), "Invalid charger availability entry for charger ID 1001!\nAvailability from is after availability to.");
+        let location = read_error.location().unwrap();
+        assert_eq!(location.line, 3);
+        assert_eq!(location.columns, 6..9);
+
+        let missing_column = read_stations_csv(&mut fleet, "station,charger_id\n".as_bytes(), &CsvOptions::default(), &ReadOptions::default());
+        assert!(matches!(missing_column, Err(UptimeError::Parse { kind: ParseErrorKind::MissingCsvColumn { .. }, .. })));
+    }
+}
@@ -0,0 +1,430 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::ops::Range;
+
+use crate::time_range::TimeRange;
+
+/// Errors returned while reading input or computing uptime.
+///
+/// Each variant maps to a distinct process exit code through `exit_code`.
+/// `Display` writes the plain error message. `render` additionally writes
+/// the location of the error in the input, compiler-style.
+#[derive(Debug)]
+pub enum UptimeError {
+    /// The command line arguments could not be used.
+    InvalidArguments { message: String },
+    /// The input could not be opened or read.
+    Io(io::Error),
+    /// A line of input could not be parsed.
+    Parse { location: Box<Location>, kind: ParseErrorKind },
+    /// A charger was listed under two different stations.
+    /// `location` is the input line of the second mapping, if read from input.
+    ChargerMappedToMultipleStations {
+        location: Option<Box<Location>>,
+        charger_id: u32,
+        station_id: u32,
+        mapped_station_id: u32
+    },
+    /// Two overlapping reports of the same charger disagree on `up` status.
+    ConflictingReports {
+        charger_id: u32,
+        first: TimeRange,
+        second: TimeRange
+    },
+    /// Chargers reported availability but are not listed under any station,
+    /// and orphaned chargers were set to be an error. IDs are in ascending order.
+    OrphanedChargers { charger_ids: Vec<u32> },
+    /// Validation found errors, or warnings when they were set to fail validation.
+    ValidationFailed { errors: usize, warnings: usize },
+    /// Input read in streaming mode is not in the order it was declared to be in.
+    /// `location` is the first input line out of order, if read from input.
+    UnsortedInput { location: Option<Box<Location>>, message: String },
+    /// Several errors collected in one pass over the input, in input order.
+    Multiple(Vec<UptimeError>)
+}
+
+/// Position of an error in the input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    /// Name of the input file, if the input was read from a file.
+    pub file: Option<String>,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based character columns of the offending text. The end is exclusive.
+    pub columns: Range<usize>,
+    /// The raw input line, without the line terminator.
+    pub source_line: String
+}
+
+/// An error in a single line of input, with the byte span of the offending
+/// text within the parsed string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub span: Range<usize>
+}
+
+/// Kinds of errors in a single line of input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// An entry was found before any `[Stations]`,
+    /// `[Charger Availability Reports]`, `[Exclusions]` or `[Charger Attributes]`
+    /// section heading.
+    MissingSectionHeader,
+    InvalidStationId { station_id: String },
+    InvalidStationChargerId { station_id: u32, charger_id: String },
+    MalformedAvailabilityEntry,
+    InvalidAvailabilityChargerId { charger_id: String },
+    InvalidStartTime { charger_id: u32, start_time: String },
+    InvalidEndTime { charger_id: u32, end_time: String },
+    InvalidUpStatus { charger_id: u32, up_status: String, entry: String },
+    InvertedTimeRange { charger_id: u32, from: u64, to: u64 },
+    /// A connector of a charger, on a station line or a connector report,
+    /// is not an unsigned 32-bit integer.
+    InvalidConnectorId { charger_id: u32, connector_id: String },
+    /// An `[Exclusions]` entry doesn't have the expected number of fields.
+    MalformedExclusion,
+    /// An exclusion applies to neither `station` nor `charger`.
+    InvalidExclusionTarget { target: String },
+    InvalidExclusionId { target: String, id: String },
+    InvalidExclusionTime { time: String },
+    InvertedExclusion { from: u64, to: u64 },
+    /// A `[Charger Attributes]` entry doesn't have the expected number of fields.
+    MalformedChargerAttributes,
+    InvalidAttributesChargerId { charger_id: String },
+    /// A power rating is not a finite number of kW above 0.
+    InvalidPowerRating { charger_id: u32, power: String },
+    /// A CSV header doesn't have an expected column.
+    MissingCsvColumn { column: String },
+    /// A CSV record could not be read, such as one with the wrong number of fields.
+    MalformedCsvRecord { message: String }
+}
+
+impl UptimeError {
+
+    /// Exit code the CLI uses for this error.
+    ///
+    /// | Variant                            | Code |
+    /// |------------------------------------|------|
+    /// | `InvalidArguments`                 | 1    |
+    /// | `Parse`                            | 2    |
+    /// | `ConflictingReports`               | 3    |
+    /// | `ChargerMappedToMultipleStations`  | 4    |
+    /// | `Io`                               | 5    |
+    /// | `OrphanedChargers`                 | 6    |
+    /// | `ValidationFailed` with errors     | 7    |
+    /// | `ValidationFailed` with warnings   | 8    |
+    /// | `UnsortedInput`                    | 9    |
+    ///
+    /// `Multiple` uses the exit code of its first error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            UptimeError::InvalidArguments { .. } => 1,
+            UptimeError::Parse { .. } => 2,
+            UptimeError::ConflictingReports { .. } => 3,
+            UptimeError::ChargerMappedToMultipleStations { .. } => 4,
+            UptimeError::Io(_) => 5,
+            UptimeError::OrphanedChargers { .. } => 6,
+            UptimeError::ValidationFailed { errors, .. } => if *errors>0 { 7 } else { 8 },
+            UptimeError::UnsortedInput { .. } => 9,
+            UptimeError::Multiple(errors) => errors.first().map_or(2, |error| error.exit_code()),
+        }
+    }
+
+    /// Short name of the kind of error, used to group skipped lines in summaries.
+    pub fn category(&self) -> &'static str {
+        match self {
+            UptimeError::InvalidArguments { .. } => "invalid arguments",
+            UptimeError::Io(_) => "unreadable input",
+            UptimeError::Parse { kind, .. } => kind.category(),
+            UptimeError::ChargerMappedToMultipleStations { .. } => "charger mapped to multiple stations",
+            UptimeError::ConflictingReports { .. } => "conflicting reports",
+            UptimeError::OrphanedChargers { .. } => "orphaned chargers",
+            UptimeError::ValidationFailed { .. } => "validation failed",
+            UptimeError::UnsortedInput { .. } => "unsorted input",
+            UptimeError::Multiple(_) => "multiple errors",
+        }
+    }
+
+    /// Location of the error in the input, if known.
+    pub fn location(&self) -> Option<&Location> {
+        match self {
+            UptimeError::Parse { location, .. } => Some(location),
+            UptimeError::ChargerMappedToMultipleStations { location, .. } => location.as_deref(),
+            UptimeError::UnsortedInput { location, .. } => location.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Sets the input file name on the location of this error, and on every
+    /// error of `Multiple`.
+    pub fn with_file(mut self, file: &str) -> UptimeError {
+        match &mut self {
+            UptimeError::Parse { location, .. } => location.file = Some(file.to_string()),
+            UptimeError::ChargerMappedToMultipleStations { location: Some(location), .. } => location.file = Some(file.to_string()),
+            UptimeError::UnsortedInput { location: Some(location), .. } => location.file = Some(file.to_string()),
+            UptimeError::Multiple(errors) => {
+                let errors_with_file = errors.drain(..).map(|error| error.with_file(file)).collect();
+                *errors = errors_with_file;
+            },
+            _ => {},
+        }
+        self
+    }
+
+    /// Renders the error message followed by its location, with a caret under
+    /// the offending text:
+    ///
+    /// ```text
+    /// ERROR: Invalid station ID: 'A'
+    ///  --> input.txt:2:1
+    ///   |
+    /// 2 | A 1001 1002
+    ///   | ^
+    /// ```
+    ///
+    /// Errors without a location render as `ERROR: <message>`. `Multiple`
+    /// renders each of its errors, separated by an empty line.
+    pub fn render(&self) -> String {
+        if let UptimeError::Multiple(errors) = self {
+            let rendered: Vec<String> = errors.iter().map(|error| error.render()).collect();
+            return rendered.join("\n\n");
+        }
+        match self.location() {
+            Some(location) => format!("ERROR: {}\n{}", self, location.render()),
+            None => format!("ERROR: {}", self),
+        }
+    }
+}
+
+impl Location {
+
+    /// Builds the location of a byte span within a raw input line.
+    /// ### Input:
+    /// - `line`: 1-based line number
+    /// - `source_line`: The raw input line
+    /// - `span`: Byte span of the offending text within `source_line`
+    pub fn new(line: usize, source_line: &str, span: Range<usize>) -> Location {
+        let column_start = source_line[..span.start].chars().count() + 1;
+        let column_end = column_start + source_line[span].chars().count();
+        Location {
+            file: None,
+            line,
+            columns: column_start..column_end,
+            source_line: source_line.to_string(),
+        }
+    }
+
+    /// Renders the ` --> file:line:column` header, the source line, and the
+    /// caret line under the offending columns.
+    fn render(&self) -> String {
+        let line_number = self.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let file = self.file.as_deref().unwrap_or("<input>");
+        // Keeping tabs in the caret line so carets line up with the source line.
+        let padding: String = self.source_line.chars()
+            .take(self.columns.start - 1)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let carets = "^".repeat((self.columns.end - self.columns.start).max(1));
+        format!("{gutter}--> {file}:{line}:{column}\n{gutter} |\n{line} | {source}\n{gutter} | {padding}{carets}",
+            gutter = gutter,
+            file = file,
+            line = line_number,
+            column = self.columns.start,
+            source = self.source_line,
+            padding = padding,
+            carets = carets)
+    }
+}
+
+impl fmt::Display for UptimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UptimeError::InvalidArguments { message } => write!(f, "{}", message),
+            UptimeError::Io(io_error) => write!(f, "{}", io_error),
+            UptimeError::Parse { kind, .. } => write!(f, "{}", kind),
+            UptimeError::ChargerMappedToMultipleStations { charger_id, station_id, mapped_station_id, .. } =>
+                write!(f, "Invalid data. The Charger ID '{}' is mapped to multiple Station IDs({}, and {})", charger_id, station_id, mapped_station_id),
+            UptimeError::ConflictingReports { charger_id, .. } =>
+                write!(f, "Conflicting availability entires found for charger {}", charger_id),
+            UptimeError::OrphanedChargers { charger_ids } => {
+                let charger_ids: Vec<String> = charger_ids.iter().map(|charger_id| charger_id.to_string()).collect();
+                write!(f, "Found {} charger(s) with availability reports that belong to no station: {}", charger_ids.len(), charger_ids.join(", "))
+            },
+            UptimeError::ValidationFailed { errors, warnings } =>
+                write!(f, "Validation failed with {} error(s) and {} warning(s).", errors, warnings),
+            UptimeError::UnsortedInput { message, .. } => write!(f, "{}", message),
+            UptimeError::Multiple(errors) => {
+                for (i, error) in errors.iter().enumerate() {
+                    if i>0 {
+                        writeln!(f)?;
+                    }
+                    write!(f, "{}", error)?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
+
+impl error::Error for UptimeError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            UptimeError::Io(io_error) => Some(io_error),
+            UptimeError::Parse { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for UptimeError {
+    fn from(io_error: io::Error) -> UptimeError {
+        UptimeError::Io(io_error)
+    }
+}
+
+impl ParseError {
+    pub fn new(kind: ParseErrorKind, span: Range<usize>) -> ParseError {
+        ParseError { kind, span }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl ParseErrorKind {
+
+    /// Short name of the kind of error, used to group skipped lines in summaries.
+    pub fn category(&self) -> &'static str {
+        match self {
+            ParseErrorKind::MissingSectionHeader => "missing section heading",
+            ParseErrorKind::InvalidStationId { .. } => "invalid station ID",
+            ParseErrorKind::InvalidStationChargerId { .. } => "invalid charger ID",
+            ParseErrorKind::MalformedAvailabilityEntry => "malformed availability entry",
+            ParseErrorKind::InvalidAvailabilityChargerId { .. } => "invalid charger ID",
+            ParseErrorKind::InvalidStartTime { .. } => "invalid start time",
+            ParseErrorKind::InvalidEndTime { .. } => "invalid end time",
+            ParseErrorKind::InvalidUpStatus { .. } => "invalid up status",
+            ParseErrorKind::InvertedTimeRange { .. } => "start time after end time",
+            ParseErrorKind::InvalidConnectorId { .. } => "invalid connector ID",
+            ParseErrorKind::MalformedExclusion => "malformed exclusion entry",
+            ParseErrorKind::InvalidExclusionTarget { .. } => "invalid exclusion target",
+            ParseErrorKind::InvalidExclusionId { .. } => "invalid exclusion ID",
+            ParseErrorKind::InvalidExclusionTime { .. } => "invalid exclusion time",
+            ParseErrorKind::InvertedExclusion { .. } => "start time after end time",
+            ParseErrorKind::MalformedChargerAttributes => "malformed charger attributes entry",
+            ParseErrorKind::InvalidAttributesChargerId { .. } => "invalid charger ID",
+            ParseErrorKind::InvalidPowerRating { .. } => "invalid power rating",
+            ParseErrorKind::MissingCsvColumn { .. } => "missing CSV column",
+            ParseErrorKind::MalformedCsvRecord { .. } => "malformed CSV record",
+        }
+    }
+}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::MissingSectionHeader =>
+                write!(f, "Invalid file format. Unable to read file."),
+            ParseErrorKind::InvalidStationId { station_id } =>
+                write!(f, "Invalid station ID: '{}'", station_id),
+            ParseErrorKind::InvalidStationChargerId { station_id, .. } =>
+                write!(f, "Invalid station entry for Station ID: {}.\nCould not parse charger ID.", station_id),
+            ParseErrorKind::MalformedAvailabilityEntry =>
+                write!(f, "Could not parse charger availability entry. Please check the input file."),
+            ParseErrorKind::InvalidAvailabilityChargerId { .. } =>
+                write!(f, "Invalid charger availability entry.\nCould not parse charger ID."),
+            ParseErrorKind::InvalidStartTime { charger_id, .. } =>
+                write!(f, "Invalid charger availability entry.\nCould not parse start time for charger ID: {}.", charger_id),
+            ParseErrorKind::InvalidEndTime { charger_id, .. } =>
+                write!(f, "Invalid charger availability entry.\nCould not parse end time for charger ID: {}.", charger_id),
+            ParseErrorKind::InvalidUpStatus { entry, .. } =>
+                write!(f, "Invalid charger availability entry.\nMissing charger up status(true/false) in entry:\n{}", entry),
+            ParseErrorKind::InvertedTimeRange { charger_id, .. } =>
+                write!(f, "Invalid charger availability entry for charger ID {}!\nAvailability from is after availability to.", charger_id),
+            ParseErrorKind::InvalidConnectorId { charger_id, connector_id } =>
+                write!(f, "Invalid connector of charger ID {}.\nCould not parse connector ID: '{}'.", charger_id, connector_id),
+            ParseErrorKind::MalformedExclusion =>
+                write!(f, "Could not parse exclusion entry. Expected: <station|charger> <ID> <start time nanos> <end time nanos> <reason>"),
+            ParseErrorKind::InvalidExclusionTarget { target } =>
+                write!(f, "Invalid exclusion entry.\nExpected 'station' or 'charger', found '{}'.", target),
+            ParseErrorKind::InvalidExclusionId { target, id } =>
+                write!(f, "Invalid exclusion entry.\nCould not parse {} ID: '{}'.", target, id),
+            ParseErrorKind::InvalidExclusionTime { time } =>
+                write!(f, "Invalid exclusion entry.\nCould not parse time: '{}'.", time),
+            ParseErrorKind::InvertedExclusion { from, to } =>
+                write!(f, "Invalid exclusion entry.\nExclusion from {} is after exclusion to {}.", from, to),
+            ParseErrorKind::MalformedChargerAttributes =>
+                write!(f, "Could not parse charger attributes entry. Expected: <Charger ID> <power kW> [<connector type> [<network>]]"),
+            ParseErrorKind::InvalidAttributesChargerId { charger_id } =>
+                write!(f, "Invalid charger attributes entry.\nCould not parse charger ID: '{}'.", charger_id),
+            ParseErrorKind::InvalidPowerRating { charger_id, power } =>
+                write!(f, "Invalid charger attributes entry for charger ID {}.\nExpected a power rating in kW above 0, found '{}'.", charger_id, power),
+            ParseErrorKind::MissingCsvColumn { column } =>
+                write!(f, "Missing column '{}' in CSV header.", column),
+            ParseErrorKind::MalformedCsvRecord { message } =>
+                write!(f, "Could not read CSV record: {}", message),
+        }
+    }
+}
+
+impl error::Error for ParseErrorKind {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_codes_are_distinct() {
+        let errors = [
+            UptimeError::InvalidArguments { message: String::new() },
+            UptimeError::Io(io::Error::new(io::ErrorKind::NotFound, "missing")),
+            UptimeError::Parse { location: Box::new(Location::new(1, "x", 0..1)), kind: ParseErrorKind::MissingSectionHeader },
+            UptimeError::ChargerMappedToMultipleStations { location: None, charger_id: 1, station_id: 1, mapped_station_id: 2 },
+            UptimeError::ConflictingReports { charger_id: 1, first: TimeRange::new(0, 2, true), second: TimeRange::new(1, 3, false) },
+            UptimeError::OrphanedChargers { charger_ids: vec![1] },
+            UptimeError::ValidationFailed { errors: 1, warnings: 0 },
+            UptimeError::ValidationFailed { errors: 0, warnings: 1 },
+            UptimeError::UnsortedInput { location: None, message: String::new() },
+        ];
+        let mut exit_codes: Vec<i32> = errors.iter().map(|error| error.exit_code()).collect();
+        exit_codes.sort();
+        exit_codes.dedup();
+        assert_eq!(exit_codes.len(), errors.len());
+    }
+
+    #[test]
+    fn parse_error_display_keeps_message() {
+        let error = UptimeError::Parse {
+            location: Box::new(Location::new(3, "A 1001", 0..1)),
+            kind: ParseErrorKind::InvalidStationId { station_id: "A".to_string() },
+        };
+        assert_eq!(error.to_string(), "Invalid station ID: 'A'");
+    }
+
+    #[test]
+    fn render_points_at_columns() {
+        let source_line = "1001 0 50000 maybe";
+        let error = UptimeError::Parse {
+            location: Box::new(Location::new(12, source_line, 13..18)),
+            kind: ParseErrorKind::InvalidUpStatus { charger_id: 1001, up_status: "maybe".to_string(), entry: source_line.to_string() },
+        }.with_file("input.txt");
+        assert_eq!(error.location().unwrap().columns, 14..19);
+        let rendered = error.render();
+        let expected_location = "  --> input.txt:12:14\n   |\n12 | 1001 0 50000 maybe\n   |              ^^^^^";
+        assert!(rendered.ends_with(expected_location), "{}", rendered);
+    }
+
+    #[test]
+    fn render_without_location() {
+        let error = UptimeError::ConflictingReports { charger_id: 7, first: TimeRange::new(0, 2, true), second: TimeRange::new(1, 3, false) };
+        assert_eq!(error.render(), "ERROR: Conflicting availability entires found for charger 7");
+    }
+}
@@ -0,0 +1,341 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::availability::{apply_window, summarize_timeline, TimelineSummary};
+use crate::time_range::TimeRange;
+use crate::window::{ReportingWindow, UnreportedTime};
+
+/// What an `Exclusion` applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ExclusionTarget {
+    /// Every charger at the station.
+    Station(u32),
+    Charger(u32)
+}
+
+impl fmt::Display for ExclusionTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExclusionTarget::Station(station_id) => write!(f, "station {}", station_id),
+            ExclusionTarget::Charger(charger_id) => write!(f, "charger {}", charger_id),
+        }
+    }
+}
+
+/// A window of excused downtime, such as scheduled maintenance or a utility
+/// outage. Excluded time is taken out of both up time and reported time.
+///
+/// The window is taken as `[from, to)`, like a `TimeRange`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Exclusion {
+    pub target: ExclusionTarget,
+    pub from: u64,
+    pub to: u64,
+    /// Reason code, such as `maintenance` or `utility_outage`. Excluded time
+    /// is broken down by it.
+    pub reason: String
+}
+
+impl Exclusion {
+
+    /// Creates a new `Exclusion`.
+    /// ### Input:
+    /// - `target`: The station or charger it applies to
+    /// - `from`: Start time in nanoseconds (inclusive)
+    /// - `to`: End time in nanoseconds (exclusive)
+    /// - `reason`: Reason code
+    ///
+    /// ### Output:
+    /// - `Exclusion`: The constructed struct. No validation is done here.
+    pub fn new(target: ExclusionTarget, from: u64, to: u64, reason: &str) -> Exclusion {
+        Exclusion { target, from, to, reason: reason.to_string() }
+    }
+}
+
+/// Disjoint windows as `(from, to)`, in ascending order.
+type Windows = Vec<(u64, u64)>;
+
+/// Excluded time of a station or a charger.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct ExclusionSet {
+    windows: Windows,
+    /// Excluded windows of each reason. Windows of different reasons can overlap.
+    reasons: BTreeMap<String, Windows>
+}
+
+impl ExclusionSet {
+
+    fn from_exclusions<'a, I>(exclusions: I) -> ExclusionSet
+        where I: IntoIterator<Item = &'a Exclusion> {
+
+        let mut reasons: BTreeMap<String, Windows> = BTreeMap::new();
+        for exclusion in exclusions {
+            reasons.entry(exclusion.reason.clone()).or_default().push((exclusion.from, exclusion.to));
+        }
+        let reasons: BTreeMap<String, Windows> = reasons.into_iter()
+            .map(|(reason, windows)| (reason, merge(windows)))
+            .filter(|(_, windows)| !windows.is_empty())
+            .collect();
+        let windows = merge(reasons.values().flatten().copied().collect());
+        ExclusionSet { windows, reasons }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.windows.is_empty()
+    }
+
+    /// Excluded time inside `[from, to)`.
+    pub fn excluded_within(&self, from: u64, to: u64) -> u64 {
+        length_within(&self.windows, from, to)
+    }
+
+    /// Excluded time of each reason inside `[from, to)`, for reasons with any.
+    pub fn reasons_within(&self, from: u64, to: u64) -> BTreeMap<String, u64> {
+        self.reasons.iter()
+            .map(|(reason, windows)| (reason.clone(), length_within(windows, from, to)))
+            .filter(|(_, excluded)| *excluded > 0)
+            .collect()
+    }
+
+    /// Cuts excluded time out of windows sorted by start time. Zero length
+    /// windows inside an exclusion are dropped.
+    ///
+    /// ### Output:
+    /// - `Vec<TimeRange>`: The parts of the windows outside of exclusions, in
+    ///   ascending order of 'from time'.
+    pub fn remove_from(&self, sorted_times: Vec<TimeRange>) -> Vec<TimeRange> {
+        if self.is_empty() {
+            return sorted_times;
+        }
+        let mut kept: Vec<TimeRange> = Vec::with_capacity(sorted_times.len());
+        for time_range in sorted_times {
+            // First excluded window that ends after the start of the range
+            let mut index = self.windows.partition_point(|(_, excluded_to)| *excluded_to <= time_range.from);
+            if time_range.from == time_range.to {
+                if self.windows.get(index).is_none_or(|(excluded_from, _)| *excluded_from > time_range.from) {
+                    kept.push(time_range);
+                }
+                continue;
+            }
+            let mut from = time_range.from;
+            while from < time_range.to {
+                match self.windows.get(index) {
+                    Some(&(excluded_from, excluded_to)) if excluded_from < time_range.to => {
+                        if excluded_from > from {
+                            kept.push(TimeRange { from, to: excluded_from, ..time_range.clone() });
+                        }
+                        from = from.max(excluded_to);
+                        index += 1;
+                    },
+                    _ => {
+                        kept.push(TimeRange { from, ..time_range.clone() });
+                        break;
+                    },
+                }
+            }
+        }
+        // Parts of a long range can start after shorter ranges that follow it
+        kept.sort();
+        kept
+    }
+
+    /// Takes excluded time out of the summary of windows.
+    ///
+    /// ### Input:
+    /// - `summary`: Summary of the windows before excluded time was cut out
+    ///   of them, without the reporting window applied
+    /// - `kept_times`: The windows with excluded time cut out, as returned by
+    ///   `remove_from`
+    /// - `window`: The reporting window, if any
+    ///
+    /// ### Output:
+    /// - `TimelineSummary`: The summary with the reporting window applied, like
+    ///   `apply_window`. Up time and reported time leave out excluded time. The
+    ///   reported span is unchanged.
+    pub fn exclude(&self, mut summary: TimelineSummary, kept_times: &[TimeRange], window: Option<&ReportingWindow>) -> TimelineSummary {
+        if !self.is_empty() {
+            summary.up_time = summarize_timeline(kept_times).map_or(0, |kept_summary| kept_summary.up_time);
+        }
+        self.exclude_span(apply_window(summary, kept_times, window), window)
+    }
+
+    /// Takes excluded time out of the reported time of a summary that already
+    /// has the reporting window applied, and whose up time leaves it out.
+    /// With `UnreportedTime::Unknown`, reported time is the time covered by
+    /// windows, which already leaves it out.
+    pub fn exclude_span(&self, mut summary: TimelineSummary, window: Option<&ReportingWindow>) -> TimelineSummary {
+        summary.excluded_time = self.excluded_within(summary.first_reported_time, summary.last_reported_time);
+        if window.is_none_or(|window| window.unreported == UnreportedTime::Down) {
+            summary.reported_time = summary.reported_time.saturating_sub(summary.excluded_time);
+        }
+        summary
+    }
+}
+
+/// Exclusions by the station or charger they apply to.
+pub(crate) struct ExclusionIndex<'a> {
+    stations: HashMap<u32, Vec<&'a Exclusion>>,
+    chargers: HashMap<u32, Vec<&'a Exclusion>>
+}
+
+impl<'a> ExclusionIndex<'a> {
+
+    pub fn new(exclusions: &'a [Exclusion]) -> ExclusionIndex<'a> {
+        let mut index = ExclusionIndex { stations: HashMap::new(), chargers: HashMap::new() };
+        for exclusion in exclusions {
+            match exclusion.target {
+                ExclusionTarget::Station(station_id) => index.stations.entry(station_id).or_default().push(exclusion),
+                ExclusionTarget::Charger(charger_id) => index.chargers.entry(charger_id).or_default().push(exclusion),
+            }
+        }
+        index
+    }
+
+    /// Excluded time of a station and of each of its chargers.
+    /// ### Input:
+    /// - `station_id`: ID of the station
+    /// - `chargers`: IDs of chargers at the station
+    ///
+    /// ### Output:
+    /// - `StationExclusions`: Each charger is excluded when the station or the
+    ///   charger itself is. The station is excluded when it is, or when all of
+    ///   its chargers are.
+    pub fn station(&self, station_id: u32, chargers: &HashSet<u32>) -> StationExclusions {
+
+        let own_exclusions: &[&Exclusion] = self.stations.get(&station_id).map_or(&[], Vec::as_slice);
+        let mut station_exclusions = StationExclusions {
+            station: ExclusionSet::from_exclusions(own_exclusions.iter().copied()),
+            chargers: HashMap::new(),
+        };
+        for charger_id in chargers {
+            if let Some(charger_exclusions) = self.chargers.get(charger_id) {
+                let exclusions = own_exclusions.iter().chain(charger_exclusions).copied();
+                station_exclusions.chargers.insert(*charger_id, ExclusionSet::from_exclusions(exclusions));
+            }
+        }
+        if chargers.is_empty() || station_exclusions.chargers.len() < chargers.len() {
+            // A charger without exclusions of its own is only excluded with the station
+            return station_exclusions;
+        }
+
+        let mut charger_sets = station_exclusions.chargers.values();
+        let first_set = charger_sets.next().unwrap();
+        let windows = charger_sets.fold(first_set.windows.clone(), |windows, charger_set| intersect(&windows, &charger_set.windows));
+        let mut reasons: BTreeMap<String, Windows> = BTreeMap::new();
+        for charger_set in station_exclusions.chargers.values() {
+            for (reason, reason_windows) in &charger_set.reasons {
+                reasons.entry(reason.clone()).or_default().extend(reason_windows);
+            }
+        }
+        station_exclusions.station = ExclusionSet {
+            reasons: reasons.into_iter()
+                .map(|(reason, reason_windows)| (reason, intersect(&merge(reason_windows), &windows)))
+                .filter(|(_, reason_windows)| !reason_windows.is_empty())
+                .collect(),
+            windows,
+        };
+        station_exclusions
+    }
+}
+
+/// Excluded time of a station, and of each of its chargers.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct StationExclusions {
+    /// Time excluded for the whole station.
+    pub station: ExclusionSet,
+    /// Time excluded for each charger with exclusions of its own, including
+    /// the station's.
+    chargers: HashMap<u32, ExclusionSet>
+}
+
+impl StationExclusions {
+
+    /// Excluded time of a charger at the station.
+    pub fn charger(&self, charger_id: u32) -> &ExclusionSet {
+        self.chargers.get(&charger_id).unwrap_or(&self.station)
+    }
+}
+
+/// Sorts windows, and merges the ones that overlap or touch. Empty windows
+/// are left out.
+fn merge(mut windows: Windows) -> Windows {
+    windows.retain(|(from, to)| from < to);
+    windows.sort();
+    let mut merged: Windows = Vec::with_capacity(windows.len());
+    for (from, to) in windows {
+        match merged.last_mut() {
+            Some(last) if from <= last.1 => last.1 = last.1.max(to),
+            _ => merged.push((from, to)),
+        }
+    }
+    merged
+}
+
+/// Time covered by both of two lists of disjoint, sorted windows.
+fn intersect(first: &[(u64, u64)], second: &[(u64, u64)]) -> Windows {
+    let mut both: Windows = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < first.len() && j < second.len() {
+        let from = first[i].0.max(second[j].0);
+        let to = first[i].1.min(second[j].1);
+        if from < to {
+            both.push((from, to));
+        }
+        if first[i].1 < second[j].1 { i += 1; } else { j += 1; }
+    }
+    both
+}
+
+/// Length of the parts of disjoint windows inside `[from, to)`.
+fn length_within(windows: &[(u64, u64)], from: u64, to: u64) -> u64 {
+    windows.iter()
+        .map(|(window_from, window_to)| (*window_to).min(to).saturating_sub((*window_from).max(from)))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exclusion(target: ExclusionTarget, from: u64, to: u64, reason: &str) -> Exclusion {
+        Exclusion::new(target, from, to, reason)
+    }
+
+    #[test]
+    fn remove_excluded_time() {
+        let exclusions = [exclusion(ExclusionTarget::Charger(1), 10, 50, "maintenance"), exclusion(ExclusionTarget::Charger(1), 60, 70, "vandalism")];
+        let set = ExclusionSet::from_exclusions(&exclusions);
+        let kept = set.remove_from(vec![TimeRange::new(0, 100, true), TimeRange::new(5, 60, false), TimeRange::new(20, 20, true)]);
+        assert_eq!(kept, vec![
+            TimeRange::new(0, 10, true),
+            TimeRange::new(5, 10, false),
+            TimeRange::new(50, 60, false),
+            TimeRange::new(50, 60, true),
+            TimeRange::new(70, 100, true),
+        ]);
+        assert_eq!(set.excluded_within(0, 65), 45);
+        assert_eq!(set.reasons_within(0, 65), BTreeMap::from([("maintenance".to_string(), 40), ("vandalism".to_string(), 5)]));
+    }
+
+    #[test]
+    fn station_is_excluded_when_all_chargers_are() {
+        let exclusions = [
+            exclusion(ExclusionTarget::Station(0), 0, 10, "utility_outage"),
+            exclusion(ExclusionTarget::Charger(1001), 20, 60, "maintenance"),
+            exclusion(ExclusionTarget::Charger(1002), 40, 80, "vandalism"),
+            exclusion(ExclusionTarget::Charger(1003), 0, 100, "maintenance"),
+        ];
+        let index = ExclusionIndex::new(&exclusions);
+        let station = index.station(0, &HashSet::from([1001, 1002]));
+        assert_eq!(station.station.windows, vec![(0, 10), (40, 60)]);
+        assert_eq!(station.station.reasons_within(0, 100), BTreeMap::from([
+            ("maintenance".to_string(), 20), ("utility_outage".to_string(), 10), ("vandalism".to_string(), 20),
+        ]));
+        assert_eq!(station.charger(1001).windows, vec![(0, 10), (20, 60)]);
+
+        // Charger 1004 has no exclusions of its own
+        let station = index.station(0, &HashSet::from([1001, 1004]));
+        assert_eq!(station.station.windows, vec![(0, 10)]);
+        assert_eq!(station.charger(1004).windows, vec![(0, 10)]);
+    }
+}
@@ -0,0 +1,458 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::BufRead;
+
+use crate::attributes::ChargerAttributes;
+use crate::availability::{compute_fleet_availability, covered_time, ComputeOptions};
+use crate::connector::roll_up_connectors;
+use crate::csv_input::{read_reports_csv, read_stations_csv, CsvOptions};
+use crate::error::UptimeError;
+use crate::exclusion::Exclusion;
+use crate::parser::{construct_maps, input_name, open_input, read_each_file, read_text_into, ReadOptions, RejectedLine};
+use crate::report::{OrphanedCharger, UptimeReport};
+use crate::time_range::TimeRange;
+
+/// A set of stations, the chargers at each station and their connectors, the
+/// availability reports of each charger or connector, windows excluded from
+/// uptime, and optional attributes of chargers.
+///
+/// A `Fleet` can be read from input in the `[Stations]` /
+/// `[Charger Availability Reports]` format, or built up with `add_station`
+/// and `add_report`.
+#[derive(Clone, Debug, Default)]
+pub struct Fleet {
+    station_charger_map: HashMap<u32, HashSet<u32>>,
+    charger_station_map: HashMap<u32, u32>,
+    charger_uptime_map: HashMap<u32, Vec<TimeRange>>,
+    connectors: HashMap<u32, BTreeSet<u32>>,
+    connector_uptime_map: HashMap<u32, BTreeMap<u32, Vec<TimeRange>>>,
+    exclusions: Vec<Exclusion>,
+    charger_attributes: HashMap<u32, ChargerAttributes>,
+    rejected_lines: Vec<RejectedLine>
+}
+
+impl Fleet {
+
+    /// Creates an empty `Fleet`.
+    pub fn new() -> Fleet {
+        Fleet::default()
+    }
+
+    /// Reads a `Fleet` from a buffered reader.
+    /// ### Input:
+    /// - `reader`: A reader over input in the `[Stations]` /
+    ///   `[Charger Availability Reports]` format
+    ///
+    /// ### Output:
+    /// - `Result<Fleet, UptimeError>`: The `Fleet` wrapped in `Ok()` if successful
+    ///   and `UptimeError` in case of error.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Fleet, UptimeError> {
+        Fleet::from_reader_with_options(reader, &ReadOptions::default())
+    }
+
+    /// Reads a `Fleet` from a buffered reader, handling invalid lines as
+    /// set in `options`.
+    pub fn from_reader_with_options<R: BufRead>(reader: R, options: &ReadOptions) -> Result<Fleet, UptimeError> {
+        construct_maps(reader.lines(), options)
+    }
+
+    /// Reads a `Fleet` from a file, or from `stdin` if the path is `-`.
+    /// ### Input:
+    /// - `file_path`: A string reference to file path
+    ///
+    /// ### Output:
+    /// - `Result<Fleet, UptimeError>`: The `Fleet` wrapped in `Ok()` if successful
+    ///   and `UptimeError` in case of error. Error locations carry `file_path`.
+    pub fn from_file(file_path: &str) -> Result<Fleet, UptimeError> {
+        Fleet::from_file_with_options(file_path, &ReadOptions::default())
+    }
+
+    /// Reads a `Fleet` from a file, handling invalid lines as set in `options`.
+    pub fn from_file_with_options(file_path: &str, options: &ReadOptions) -> Result<Fleet, UptimeError> {
+        let mut fleet = Fleet::new();
+        fleet.read_file(file_path, options)?;
+        Ok(fleet)
+    }
+
+    /// Reads a `Fleet` from several files, merging their `[Stations]` and
+    /// `[Charger Availability Reports]` sections. Each file is read on its own,
+    /// so it has to start with a section heading like a single input file.
+    /// ### Input:
+    /// - `file_paths`: File paths in the order to read them. `-` reads `stdin`
+    /// - `options`: Options for handling invalid lines
+    ///
+    /// ### Output:
+    /// - `Result<Fleet, UptimeError>`: The `Fleet` wrapped in `Ok()` if successful
+    ///   and `UptimeError` in case of error. With `collect_errors`, the errors of
+    ///   all files are returned together.
+    pub fn from_files_with_options(file_paths: &[String], options: &ReadOptions) -> Result<Fleet, UptimeError> {
+        let mut fleet = Fleet::new();
+        read_each_file(file_paths, options, |file_path| fleet.read_file(file_path, options))?;
+        Ok(fleet)
+    }
+
+    /// Reads a file in the `[Stations]` / `[Charger Availability Reports]`
+    /// format into this fleet, or `stdin` if the path is `-`.
+    /// ### Input:
+    /// - `file_path`: A string reference to file path
+    /// - `options`: Options for handling invalid lines
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError` in case of error. Error
+    ///   locations carry `file_path`, or `<stdin>`.
+    pub fn read_file(&mut self, file_path: &str, options: &ReadOptions) -> Result<(), UptimeError> {
+        let rejected_before = self.rejected_lines.len();
+        let reader = open_input(file_path)?;
+        read_text_into(self, reader.lines(), options)
+            .map_err(|uptime_error| uptime_error.with_file(input_name(file_path)))?;
+        self.set_rejected_file(rejected_before, input_name(file_path));
+        Ok(())
+    }
+
+    /// Reads station mappings from a CSV file into this fleet, or from `stdin`
+    /// if the path is `-`.
+    /// ### Input:
+    /// - `file_path`: A string reference to file path
+    /// - `csv_options`: Delimiter and header names
+    /// - `read_options`: Options for handling invalid records
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError` in case of error. Error
+    ///   locations carry `file_path`.
+    pub fn read_stations_csv_file(&mut self, file_path: &str, csv_options: &CsvOptions, read_options: &ReadOptions) -> Result<(), UptimeError> {
+        let rejected_before = self.rejected_lines.len();
+        let reader = open_input(file_path)?;
+        read_stations_csv(self, reader, csv_options, read_options)
+            .map_err(|uptime_error| uptime_error.with_file(input_name(file_path)))?;
+        self.set_rejected_file(rejected_before, input_name(file_path));
+        Ok(())
+    }
+
+    /// Reads charger availability reports from a CSV file into this fleet, or
+    /// from `stdin` if the path is `-`.
+    /// ### Input:
+    /// - `file_path`: A string reference to file path
+    /// - `csv_options`: Delimiter and header names
+    /// - `read_options`: Options for handling invalid records
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError` in case of error. Error
+    ///   locations carry `file_path`.
+    pub fn read_reports_csv_file(&mut self, file_path: &str, csv_options: &CsvOptions, read_options: &ReadOptions) -> Result<(), UptimeError> {
+        let rejected_before = self.rejected_lines.len();
+        let reader = open_input(file_path)?;
+        read_reports_csv(self, reader, csv_options, read_options)
+            .map_err(|uptime_error| uptime_error.with_file(input_name(file_path)))?;
+        self.set_rejected_file(rejected_before, input_name(file_path));
+        Ok(())
+    }
+
+    /// Sets the file name on lines rejected since `rejected_before` lines.
+    pub(crate) fn set_rejected_file(&mut self, rejected_before: usize, file_path: &str) {
+        for rejected in &mut self.rejected_lines[rejected_before..] {
+            rejected.location.file = Some(file_path.to_string());
+        }
+    }
+
+    /// Adds chargers to a station. The station is created if it doesn't exist.
+    /// Adding to an existing station adds more chargers to it.
+    /// ### Input:
+    /// - `station_id`: ID of the station
+    /// - `chargers`: IDs of chargers at the station
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError::ChargerMappedToMultipleStations` if
+    ///   any of the chargers is already mapped to a different station. The fleet is
+    ///   unchanged in that case.
+    pub fn add_station(&mut self, station_id: u32, chargers: &[u32]) -> Result<(), UptimeError> {
+        for charger in chargers {
+            if let Some(mapped_station) = self.charger_station_map.get(charger) {
+                if *mapped_station!=station_id {
+                    return Err(UptimeError::ChargerMappedToMultipleStations {
+                        location: None,
+                        charger_id: *charger,
+                        station_id,
+                        mapped_station_id: *mapped_station,
+                    });
+                }
+            }
+        }
+        for charger in chargers {
+            self.charger_station_map.insert(*charger, station_id);
+        }
+        self.station_charger_map.entry(station_id)
+            .or_default()
+            .extend(chargers);
+        Ok(())
+    }
+
+    /// Adds an availability report for a charger. The charger does not need to
+    /// be mapped to a station.
+    /// ### Input:
+    /// - `charger_id`: ID of the reporting charger
+    /// - `time_range`: The reported window and up/down status
+    pub fn add_report(&mut self, charger_id: u32, time_range: TimeRange) {
+        self.charger_uptime_map.entry(charger_id)
+            .or_default()
+            .push(time_range);
+    }
+
+    /// Adds connectors to a charger. Reports of the listed connectors roll up
+    /// to the charger, and are combined with the charger's own reports, if it
+    /// has any, like overlapping reports of the charger. The charger does not
+    /// need to be mapped to a station.
+    pub fn add_connectors(&mut self, charger_id: u32, connectors: &[u32]) {
+        self.connectors.entry(charger_id)
+            .or_default()
+            .extend(connectors);
+    }
+
+    /// Adds an availability report for a connector of a charger. The
+    /// connector does not need to be added with `add_connectors`.
+    /// ### Input:
+    /// - `charger_id`: ID of the charger
+    /// - `connector_id`: ID of the reporting connector, unique within the charger
+    /// - `time_range`: The reported window and up/down status
+    pub fn add_connector_report(&mut self, charger_id: u32, connector_id: u32, time_range: TimeRange) {
+        self.connector_uptime_map.entry(charger_id)
+            .or_default()
+            .entry(connector_id)
+            .or_default()
+            .push(time_range);
+    }
+
+    /// Adds a window of excused downtime of a station or charger. The station
+    /// or charger does not need to exist.
+    pub fn add_exclusion(&mut self, exclusion: Exclusion) {
+        self.exclusions.push(exclusion);
+    }
+
+    /// Sets the attributes of a charger, replacing any set before. The charger
+    /// does not need to be mapped to a station.
+    pub fn add_charger_attributes(&mut self, charger_id: u32, attributes: ChargerAttributes) {
+        self.charger_attributes.insert(charger_id, attributes);
+    }
+
+    /// Computes the uptime of every station that has at least one report.
+    /// ### Output:
+    /// - `Result<UptimeReport, UptimeError>`: The report wrapped in `Ok()` if successful
+    ///   and `UptimeError` in case of conflicting reports.
+    pub fn compute(&self) -> Result<UptimeReport, UptimeError> {
+        self.compute_with_options(&ComputeOptions::default())
+    }
+
+    /// Computes the uptime of every station that has at least one report,
+    /// as set in `options`. Connector reports are rolled up to their chargers
+    /// first, and connector uptimes are added to the report.
+    pub fn compute_with_options(&self, options: &ComputeOptions) -> Result<UptimeReport, UptimeError> {
+        if self.connector_uptime_map.is_empty() {
+            return compute_fleet_availability(&self.station_charger_map, &self.charger_uptime_map, &self.exclusions, &self.charger_attributes, options);
+        }
+        let rolled_up = roll_up_connectors(self, options)?;
+        let mut report = compute_fleet_availability(&self.station_charger_map, &rolled_up.charger_uptime_map, &self.exclusions, &self.charger_attributes, options)?;
+        report.set_connectors(rolled_up.connectors);
+        for (charger_id, conflicts) in rolled_up.conflicts_resolved {
+            *report.conflicts_resolved.entry(charger_id).or_default() += conflicts;
+        }
+        Ok(report)
+    }
+
+    /// A map of Station ID to IDs of chargers at the station.
+    pub fn station_charger_map(&self) -> &HashMap<u32, HashSet<u32>> {
+        &self.station_charger_map
+    }
+
+    /// A map of Charger ID to `TimeRange` structs for the charger.
+    pub fn charger_uptime_map(&self) -> &HashMap<u32, Vec<TimeRange>> {
+        &self.charger_uptime_map
+    }
+
+    /// A map of Charger ID to IDs of its connectors, for chargers with connectors.
+    pub fn connectors(&self) -> &HashMap<u32, BTreeSet<u32>> {
+        &self.connectors
+    }
+
+    /// A map of Charger ID to Connector ID to `TimeRange` structs for the connector.
+    pub fn connector_uptime_map(&self) -> &HashMap<u32, BTreeMap<u32, Vec<TimeRange>>> {
+        &self.connector_uptime_map
+    }
+
+    /// Windows excluded from uptime, in input order.
+    pub fn exclusions(&self) -> &[Exclusion] {
+        &self.exclusions
+    }
+
+    /// A map of Charger ID to attributes of the charger, for chargers with any.
+    pub fn charger_attributes(&self) -> &HashMap<u32, ChargerAttributes> {
+        &self.charger_attributes
+    }
+
+    /// Input lines skipped while reading in lenient mode, in input order,
+    /// with the error found in each.
+    pub fn rejected_lines(&self) -> &[RejectedLine] {
+        &self.rejected_lines
+    }
+
+    /// Number of skipped input lines for each kind of error.
+    pub fn rejected_line_counts(&self) -> BTreeMap<&'static str, usize> {
+        let mut counts: BTreeMap<&'static str, usize> = BTreeMap::new();
+        for rejected in &self.rejected_lines {
+            *counts.entry(rejected.category).or_default() += 1;
+        }
+        counts
+    }
+
+    /// Records an input line skipped in lenient mode.
+    pub(crate) fn reject_line(&mut self, rejected: RejectedLine) {
+        self.rejected_lines.push(rejected);
+    }
+
+    /// Chargers with availability reports that are not listed under any
+    /// station, in ascending order of Charger ID. These are usually mistakes
+    /// in the station mapping, since their reports are left out of uptime.
+    /// Connector reports count as reports of their charger.
+    pub fn orphaned_chargers(&self) -> Vec<OrphanedCharger> {
+        let reporting_chargers: BTreeSet<u32> = self.charger_uptime_map.keys()
+            .chain(self.connector_uptime_map.keys())
+            .copied()
+            .collect();
+        let mut orphaned_chargers: Vec<OrphanedCharger> = reporting_chargers.into_iter()
+            .filter(|charger_id| !self.charger_station_map.contains_key(charger_id))
+            .map(|charger_id| {
+                let connector_times = self.connector_uptime_map.get(&charger_id).into_iter().flat_map(BTreeMap::values);
+                let mut sorted_times: Vec<TimeRange> = self.charger_uptime_map.get(&charger_id).into_iter()
+                    .chain(connector_times)
+                    .flatten()
+                    .cloned()
+                    .collect();
+                sorted_times.sort();
+                OrphanedCharger {
+                    charger_id,
+                    report_count: sorted_times.len(),
+                    reported_nanos: covered_time(&sorted_times),
+                }
+            })
+            .collect();
+        orphaned_chargers.sort_by_key(|orphaned_charger| orphaned_charger.charger_id);
+        orphaned_chargers
+    }
+
+    /// Fails with `UptimeError::OrphanedChargers` if any charger with
+    /// availability reports is not listed under a station.
+    pub fn check_orphaned_chargers(&self) -> Result<(), UptimeError> {
+        let orphaned_chargers = self.orphaned_chargers();
+        if orphaned_chargers.is_empty() {
+            return Ok(());
+        }
+        Err(UptimeError::OrphanedChargers {
+            charger_ids: orphaned_chargers.iter().map(|orphaned_charger| orphaned_charger.charger_id).collect(),
+        })
+    }
+
+    /// The Station ID a charger is mapped to, if any.
+    pub fn station_of(&self, charger_id: u32) -> Option<u32> {
+        self.charger_station_map.get(&charger_id).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ParseErrorKind;
+    use crate::percent::UptimePercent;
+
+    const SAMPLE_INPUT: &str = "\
+[Stations]
+0 1001 1002
+1 1003
+2 1004
+
+[Charger Availability Reports]
+1001 0 50000 true
+1001 50000 100000 true
+1002 50000 100000 true
+1003 25000 75000 false
+1004 0 50000 true
+1004 100000 200000 true";
+
+    #[test]
+    fn fleet_from_reader_sample() {
+        let fleet = Fleet::from_reader(SAMPLE_INPUT.as_bytes()).unwrap();
+        let report = fleet.compute().unwrap();
+        assert_eq!(report.to_string(), "0 100\n1 0\n2 75");
+    }
+
+    #[test]
+    fn fleet_built_by_hand() {
+        let mut fleet = Fleet::new();
+        fleet.add_station(7, &[1, 2]).unwrap();
+        fleet.add_report(1, TimeRange::new(0, 100, true));
+        fleet.add_report(2, TimeRange::new(100, 200, false));
+        let report = fleet.compute().unwrap();
+        assert_eq!(report.get(7).unwrap().uptime_percent, Some(UptimePercent::from(50)));
+        assert!(report.get(8).is_none());
+    }
+
+    #[test]
+    fn fleet_charger_mapped_twice() {
+        let mut fleet = Fleet::new();
+        fleet.add_station(0, &[1001]).unwrap();
+        let add_result = fleet.add_station(1, &[1001, 1002]);
+        assert!(matches!(add_result, Err(UptimeError::ChargerMappedToMultipleStations {
+            location: None, charger_id: 1001, station_id: 1, mapped_station_id: 0
+        })));
+        assert_eq!(fleet.station_of(1001), Some(0));
+        assert_eq!(fleet.station_of(1002), None);
+    }
+
+    #[test]
+    fn fleet_from_reader_reports_line() {
+        let input = "[Stations]\n0 1001\n1 1001\n";
+        match Fleet::from_reader(input.as_bytes()) {
+            Err(UptimeError::ChargerMappedToMultipleStations { location, .. }) => assert_eq!(location.unwrap().line, 3),
+            other => panic!("Expected mapping error, got {:?}", other),
+        }
+        let input = "[Charger Availability Reports]\n\n1001 0 10 maybe\n";
+        match Fleet::from_reader(input.as_bytes()) {
+            Err(UptimeError::Parse { location, kind: ParseErrorKind::InvalidUpStatus { charger_id, up_status, .. } }) => {
+                assert_eq!(location.line, 3);
+                assert_eq!(charger_id, 1001);
+                assert_eq!(up_status, "maybe");
+            },
+            other => panic!("Expected parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn from_files_merges_sections() {
+        let directory = std::env::temp_dir().join(format!("charger-uptime-merge-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let stations_path = directory.join("stations.txt").to_string_lossy().into_owned();
+        let day_1_path = directory.join("day-1.txt").to_string_lossy().into_owned();
+        let day_2_path = directory.join("day-2.txt").to_string_lossy().into_owned();
+        std::fs::write(&stations_path, "[Stations]\n0 1001\n").unwrap();
+        std::fs::write(&day_1_path, "[Charger Availability Reports]\n1001 0 50 true\n").unwrap();
+        std::fs::write(&day_2_path, "[Charger Availability Reports]\n1001 50 100 false\n1001 x\n").unwrap();
+
+        let options = ReadOptions { collect_errors: true, ..ReadOptions::default() };
+        let merge_result = Fleet::from_files_with_options(&[stations_path.clone(), day_1_path.clone(), day_2_path.clone()], &options);
+        let location = merge_result.unwrap_err().location().cloned().unwrap();
+        assert_eq!((location.file.as_deref(), location.line), (Some(day_2_path.as_str()), 3));
+
+        std::fs::write(&day_2_path, "[Charger Availability Reports]\n1001 50 100 false\n").unwrap();
+        let fleet = Fleet::from_files_with_options(&[stations_path, day_1_path, day_2_path], &options).unwrap();
+        assert_eq!(fleet.compute().unwrap().to_string(), "0 50");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn orphaned_chargers_are_listed() {
+        let input = "[Stations]\n0 1001\n[Charger Availability Reports]\n1001 0 10 true\n1005 0 50 true\n1005 25 100 false\n1004 5 5 true\n";
+        let fleet = Fleet::from_reader(input.as_bytes()).unwrap();
+        assert_eq!(fleet.orphaned_chargers(), vec![
+            OrphanedCharger { charger_id: 1004, report_count: 1, reported_nanos: 0 },
+            OrphanedCharger { charger_id: 1005, report_count: 2, reported_nanos: 100 },
+        ]);
+        assert!(matches!(fleet.check_orphaned_chargers(), Err(UptimeError::OrphanedChargers { charger_ids }) if charger_ids == vec![1004, 1005]));
+        assert!(Fleet::from_reader(SAMPLE_INPUT.as_bytes()).unwrap().check_orphaned_chargers().is_ok());
+    }
+}
@@ -0,0 +1,210 @@
+use std::io::{self, Write};
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::time_range::TimeRange;
+
+/// First Charger ID of generated input. Chargers are numbered from it in
+/// order of station.
+pub const FIRST_CHARGER_ID: u32 = 1000;
+
+/// Options for generating synthetic input with `generate`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GenerateOptions {
+    /// Number of stations.
+    pub stations: u32,
+    /// Number of chargers at each station.
+    pub chargers_per_station: u32,
+    /// Number of reports of each charger, not counting injected overlaps
+    /// and conflicts.
+    pub reports_per_charger: u32,
+    /// Average length of a report in nanoseconds. Each report is between half
+    /// and one and a half times as long.
+    pub report_duration: u64,
+    /// Start of the reports in nanoseconds since the Unix epoch.
+    pub start: u64,
+    /// Probability of a report being down, from 0 to 1.
+    pub outage_rate: f64,
+    /// Probability of adding a report that overlaps a report with the same
+    /// `up` status, from 0 to 1.
+    pub overlap_rate: f64,
+    /// Probability of adding a report that overlaps a report and disagrees on
+    /// `up` status, from 0 to 1.
+    pub conflict_rate: f64,
+    /// Write reports in random order, instead of grouped by charger in
+    /// ascending order of start time.
+    pub shuffle: bool,
+    /// Seed of the random generator. The same options always generate the same input.
+    pub seed: u64
+}
+
+impl Default for GenerateOptions {
+    /// 100 stations of 4 chargers, with a month of hourly reports from
+    /// 2024-01-01, and 2% outages.
+    fn default() -> GenerateOptions {
+        GenerateOptions {
+            stations: 100,
+            chargers_per_station: 4,
+            reports_per_charger: 30 * 24,
+            report_duration: 3_600_000_000_000,
+            start: 1_704_067_200_000_000_000,
+            outage_rate: 0.02,
+            overlap_rate: 0.0,
+            conflict_rate: 0.0,
+            shuffle: false,
+            seed: 0,
+        }
+    }
+}
+
+/// Writes synthetic input in the `[Stations]` / `[Charger Availability Reports]`
+/// format.
+///
+/// Reports of a charger follow each other without gaps. Injected overlaps and
+/// conflicts are inside the report they overlap. Unless `shuffle` is set, the
+/// reports of each charger are written in ascending order of start time.
+///
+/// ### Input:
+/// - `writer`: Where to write the input
+/// - `options`: Size and shape of the input
+///
+/// ### Output:
+/// - `Result<(), io::Error>`: `io::Error` in case writing fails, or if there are
+///   too many chargers for 32-bit Charger IDs. With `shuffle`, all reports are
+///   held in memory before writing them.
+pub fn generate<W: Write>(writer: &mut W, options: &GenerateOptions) -> io::Result<()> {
+
+    let charger_count = options.stations.checked_mul(options.chargers_per_station)
+        .filter(|charger_count| *charger_count <= u32::MAX - FIRST_CHARGER_ID)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput,
+            format!("{} stations of {} chargers don't fit in 32-bit Charger IDs.", options.stations, options.chargers_per_station)))?;
+    let mut rng = StdRng::seed_from_u64(options.seed);
+    writeln!(writer, "[Stations]")?;
+    for station_id in 0..options.stations {
+        write!(writer, "{}", station_id)?;
+        for charger in 0..options.chargers_per_station {
+            write!(writer, " {}", FIRST_CHARGER_ID + station_id * options.chargers_per_station + charger)?;
+        }
+        writeln!(writer)?;
+    }
+    writeln!(writer)?;
+    writeln!(writer, "[Charger Availability Reports]")?;
+
+    let mut shuffled_reports: Vec<(u32, TimeRange)> = Vec::new();
+    for charger_id in FIRST_CHARGER_ID..FIRST_CHARGER_ID + charger_count {
+        let mut reports: Vec<TimeRange> = Vec::new();
+        let mut from = options.start.saturating_add(rng.gen_range(0..options.report_duration.max(1)));
+        for _ in 0..options.reports_per_charger {
+            let duration = rng.gen_range(options.report_duration / 2..=options.report_duration.saturating_mul(3) / 2);
+            let to = from.saturating_add(duration);
+            let up = !rng.gen_bool(options.outage_rate);
+            reports.push(TimeRange::new(from, to, up));
+            if to > from && rng.gen_bool(options.overlap_rate) {
+                reports.push(overlapping(&mut rng, from, to, up));
+            }
+            if to > from && rng.gen_bool(options.conflict_rate) {
+                reports.push(overlapping(&mut rng, from, to, !up));
+            }
+            from = to;
+        }
+        if options.shuffle {
+            shuffled_reports.extend(reports.into_iter().map(|report| (charger_id, report)));
+        } else {
+            // An overlap and a conflict injected into the same report can
+            // start in either order
+            reports.sort_by_key(|report| report.from);
+            for report in &reports {
+                write_report(writer, charger_id, report)?;
+            }
+        }
+    }
+    shuffled_reports.shuffle(&mut rng);
+    for (charger_id, report) in &shuffled_reports {
+        write_report(writer, *charger_id, report)?;
+    }
+    Ok(())
+}
+
+/// A report with `up` status inside `[from, to)`. It doesn't reach into the
+/// next report, so only reports injected as conflicts disagree on `up` status.
+fn overlapping(rng: &mut StdRng, from: u64, to: u64, up: bool) -> TimeRange {
+    let overlap_from = rng.gen_range(from..to);
+    let overlap_to = rng.gen_range(overlap_from + 1..=to);
+    TimeRange::new(overlap_from, overlap_to, up)
+}
+
+fn write_report<W: Write>(writer: &mut W, charger_id: u32, report: &TimeRange) -> io::Result<()> {
+    writeln!(writer, "{} {} {} {}", charger_id, report.from, report.to, report.up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::BufRead;
+    use crate::availability::ComputeOptions;
+    use crate::conflict::ConflictPolicy;
+    use crate::error::UptimeError;
+    use crate::fleet::Fleet;
+    use crate::parser::ReadOptions;
+    use crate::stream::{StreamOrder, UptimeStream};
+
+    fn generated(options: &GenerateOptions) -> String {
+        let mut output: Vec<u8> = Vec::new();
+        generate(&mut output, options).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn small_options() -> GenerateOptions {
+        GenerateOptions { stations: 3, chargers_per_station: 2, reports_per_charger: 50, seed: 7, ..GenerateOptions::default() }
+    }
+
+    #[test]
+    fn same_seed_same_input() {
+        let options = small_options();
+        let input = generated(&options);
+        assert_eq!(input, generated(&options));
+        assert_ne!(input, generated(&GenerateOptions { seed: 8, ..small_options() }));
+        assert!(input.starts_with("[Stations]\n0 1000 1001\n1 1002 1003\n2 1004 1005\n\n[Charger Availability Reports]\n"));
+        assert_eq!(input.lines().count(), 6 + 6 * 50);
+
+        let report = Fleet::from_reader(input.as_bytes()).unwrap().compute().unwrap();
+        assert_eq!(report.stations.len(), 3);
+    }
+
+    #[test]
+    fn injects_conflicts() {
+        let overlapping = GenerateOptions { overlap_rate: 0.5, ..small_options() };
+        assert!(Fleet::from_reader(generated(&overlapping).as_bytes()).unwrap().compute().is_ok());
+
+        let options = GenerateOptions { conflict_rate: 0.2, overlap_rate: 0.2, ..small_options() };
+        let fleet = Fleet::from_reader(generated(&options).as_bytes()).unwrap();
+        assert!(matches!(fleet.compute(), Err(UptimeError::ConflictingReports { .. })));
+        let compute_options = ComputeOptions { conflict_policy: ConflictPolicy::DownWins, ..ComputeOptions::default() };
+        assert!(!fleet.compute_with_options(&compute_options).unwrap().conflicts_resolved.is_empty());
+    }
+
+    #[test]
+    fn unshuffled_reports_stream_by_charger() {
+        let options = GenerateOptions { conflict_rate: 0.3, overlap_rate: 0.3, ..small_options() };
+        let input = generated(&options);
+        let compute_options = ComputeOptions { conflict_policy: ConflictPolicy::DownWins, ..ComputeOptions::default() };
+        let mut stream = UptimeStream::new(StreamOrder::Charger, compute_options.clone());
+        stream.read_lines(input.as_bytes().lines(), &ReadOptions::default()).unwrap();
+        let batch = Fleet::from_reader(input.as_bytes()).unwrap().compute_with_options(&compute_options).unwrap();
+        assert_eq!(stream.finish().unwrap().to_string(), batch.to_string());
+    }
+
+    #[test]
+    fn shuffle_keeps_reports() {
+        let sorted_lines = |input: String| {
+            let mut lines: Vec<String> = input.lines().map(str::to_string).collect();
+            lines.sort();
+            lines
+        };
+        let shuffled = generated(&GenerateOptions { shuffle: true, ..small_options() });
+        assert_ne!(shuffled, generated(&small_options()));
+        assert_eq!(sorted_lines(shuffled), sorted_lines(generated(&small_options())));
+    }
+}
@@ -0,0 +1,59 @@
+//! Computes station uptime from charger availability reports.
+//!
+//! **Station Uptime** is the percentage of time that any charger at a station
+//! was available, out of the entire time period that any charger *at that
+//! station* was reporting in. Other station metrics, such as the time all
+//! chargers were up, can be set in `ComputeOptions::station_metric`.
+//!
+//! ```
+//! use charger_uptime::Fleet;
+//!
+//! let input = "[Stations]\n0 1001\n\n[Charger Availability Reports]\n1001 0 100 true\n";
+//! let fleet = Fleet::from_reader(input.as_bytes()).unwrap();
+//! let report = fleet.compute().unwrap();
+//! assert_eq!(report.to_string(), "0 100");
+//! ```
+
+pub mod attributes;
+pub mod availability;
+pub mod bucket;
+pub mod compression;
+pub mod conflict;
+pub mod connector;
+pub mod csv_input;
+pub mod error;
+pub mod exclusion;
+pub mod fleet;
+pub mod generate;
+pub mod metric;
+pub mod output;
+pub mod parser;
+pub mod percent;
+pub mod report;
+pub mod status;
+pub mod stream;
+pub mod time_range;
+pub mod validate;
+pub mod window;
+
+pub use attributes::ChargerAttributes;
+pub use availability::{ComputeOptions, NoDataPolicy};
+pub use bucket::BucketWidth;
+pub use compression::Compression;
+pub use conflict::ConflictPolicy;
+pub use connector::ConnectorRollup;
+pub use csv_input::{CsvColumns, CsvOptions};
+pub use error::{Location, ParseError, ParseErrorKind, UptimeError};
+pub use exclusion::{Exclusion, ExclusionTarget};
+pub use fleet::Fleet;
+pub use generate::{generate, GenerateOptions};
+pub use metric::StationMetric;
+pub use output::{write_report, OutputFormat, RecordKind};
+pub use parser::{expand_input_paths, ReadOptions, RejectedLine};
+pub use percent::{PercentFormat, Rounding, UptimePercent, MAX_PRECISION};
+pub use report::{BucketUptime, ChargerUptime, ConnectorUptime, ExcludedTime, OrphanedCharger, StationStatus, StationUptime, UptimeReport};
+pub use status::{ChargerStatus, UpStatuses};
+pub use stream::{StreamOrder, UptimeStream};
+pub use time_range::TimeRange;
+pub use validate::{Check, Finding, Severity, ValidationReport, Validator};
+pub use window::{ReportingWindow, UnreportedTime};
@@ -1,557 +1,476 @@
-
 use std::fs::File;
-use std::io::{Lines, BufReader, BufRead, Error};
-use std::collections::{HashMap, HashSet};
-use regex::Regex;
+use std::io::{self, BufWriter, Write};
 use std::process;
-use std::cmp::Ordering;
 use std::env::args;
 use std::result::Result;
-use std::io::ErrorKind;
 
-enum InputKind {
-    None, 
-    Station,
-    ChargerAvailability
+use charger_uptime::{expand_input_paths, write_report, BucketWidth, ComputeOptions, ConflictPolicy, ConnectorRollup, NoDataPolicy, CsvOptions, Fleet, generate, GenerateOptions, OrphanedCharger, OutputFormat, ReadOptions,
+                     RecordKind, ReportingWindow, Rounding, StationMetric, StreamOrder, UnreportedTime, UpStatuses, UptimeError, UptimeReport, UptimeStream, Validator, MAX_PRECISION};
+
+/// Options read from the command line.
+struct Options {
+    input_paths: Vec<String>,
+    stations_csv_path: Option<String>,
+    reports_csv_path: Option<String>,
+    csv_options: CsvOptions,
+    read_options: ReadOptions,
+    rejected_lines_path: Option<String>,
+    compute_options: ComputeOptions,
+    orphans_as_error: bool,
+    stream_order: Option<StreamOrder>,
+    records: RecordKind,
+    format: OutputFormat
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Ord)]
-struct TimeRange {
-    from: u64,
-    to: u64,
-    up: bool
-}
+fn main() {
 
-impl PartialOrd for TimeRange {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        if self.from==other.from {
-            return Some(self.to.cmp(&other.to));
-        } else {
-            return Some(self.from.cmp(&other.from));
-        }
+    if let Err(uptime_error) = run() {
+        eprintln!("{}", uptime_error.render());
+        process::exit(uptime_error.exit_code());
     }
 }
 
-fn main() {
+/// Reads the input files, computes station uptime, and prints it to `stdout`.
+///
+/// ### Output:
+/// - `Result<(), UptimeError>`: `UptimeError` in case of error. The caller
+///   decides the exit code based on the error.
+fn run() -> Result<(), UptimeError> {
 
-    let file_path_wrapped = get_file_path();
-    if let Err(file_path_error) = file_path_wrapped {
-        eprintln!("ERROR: {}", file_path_error);
-        process::exit(1);
-    }
-    let construct_map_result = construct_maps(&file_path_wrapped.unwrap());
-    if let Err(construct_map_error) = construct_map_result {
-        eprintln!("ERROR: {}", construct_map_error);
-        process::exit(2);
-    }
-    let (station_charger_map, charger_uptime_map) = construct_map_result.unwrap();
-    let availability_result = compute_availability(station_charger_map, charger_uptime_map);
-    if let Err(availability_error) = availability_result {
-        eprintln!("ERROR: {}", availability_error);
-        process::exit(3);
+    let args: Vec<String> = args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("validate") => return run_validate(&args[1..]),
+        Some("generate") => return run_generate(&args[1..]),
+        _ => {},
+    }
+    let options = parse_args(&args)?;
+    let file_paths = expand_input_paths(&options.input_paths)?;
+    let report = match options.stream_order {
+        Some(stream_order) => compute_streaming(&file_paths, stream_order, &options)?,
+        None => compute(&file_paths, &options)?,
+    };
+    if !report.conflicts_resolved.is_empty() {
+        let conflicts: usize = report.conflicts_resolved.values().sum();
+        eprintln!("WARNING: Resolved {} conflicting overlap(s) in {} charger(s) with the '{}' conflict policy.",
+            conflicts, report.conflicts_resolved.len(), options.compute_options.conflict_policy);
     }
-    let mut station_availability = availability_result.unwrap();
-    station_availability.sort_by(|a,b| a.0.cmp(&b.0));
 
-    // Printing without trailing newline character
-    if let Some((station_id, availability_percent)) = station_availability.first() {
-        print!("{} {}", station_id, availability_percent);
-    }
-    for i in 1..station_availability.len() {
-        let (station_id, availability_percent) = station_availability[i];
-        print!("\n{} {}", station_id, availability_percent);
-    }
+    // Text output is printed without trailing newline character
+    let mut stdout = io::stdout().lock();
+    write_report(&mut stdout, &report, options.format, options.records)?;
+    stdout.flush()?;
+    Ok(())
 }
 
 
-/// Looks for the file path in CLI params. If not found, prints error
-/// message to stderr, and exits. If found, returns file path string.
-/// 
-/// ### Input: This function does not have any input params
-///
-/// ### Output: 
-/// - `file_path`: A file path String.
-fn get_file_path() -> Result<String, Error> {
+/// Reads all input into a `Fleet`, and computes station uptime over it.
+fn compute(file_paths: &[String], options: &Options) -> Result<UptimeReport, UptimeError> {
 
-    let args: Vec<String> = args().collect();
-    if args.len()<2 {
-        return Err(Error::new(ErrorKind::InvalidInput, format!("Missing file path parameter. Please pass a relative file path.")));
+    let mut fleet = Fleet::from_files_with_options(file_paths, &options.read_options)?;
+    if let Some(stations_csv_path) = &options.stations_csv_path {
+        fleet.read_stations_csv_file(stations_csv_path, &options.csv_options, &options.read_options)?;
     }
-
-    // The path to the target binary will be passed as the first argument.
-    // Hence the `args[1]` here
-    Ok(args[1].clone())
+    if let Some(reports_csv_path) = &options.reports_csv_path {
+        fleet.read_reports_csv_file(reports_csv_path, &options.csv_options, &options.read_options)?;
+    }
+    report_rejected_lines(&fleet, options.rejected_lines_path.as_deref())?;
+    report_orphaned_chargers(&fleet.orphaned_chargers());
+    if options.orphans_as_error {
+        fleet.check_orphaned_chargers()?;
+    }
+    fleet.compute_with_options(&options.compute_options)
 }
 
-/// Takes in a string reference to a file path, and returns an iterator of lines
-/// ### Input: 
-/// - `file_path`: A string reference to file path
-/// 
-/// ### Output:
-/// - `Result<Lines, Error>`: An iterator of lines wrapped in `Ok()` if successful 
-/// and `Error` in case of error.
-fn read_lines(file_path: &str) -> Result<Lines<BufReader<File>>, Error> {
+/// Computes station uptime while reading sorted input, with an `UptimeStream`.
+fn compute_streaming(file_paths: &[String], stream_order: StreamOrder, options: &Options) -> Result<UptimeReport, UptimeError> {
 
-    let file: File = File::open(file_path)?;
-    Ok(BufReader::new(file).lines())
+    let mut stream = UptimeStream::new(stream_order, options.compute_options.clone());
+    stream.read_files(file_paths, &options.read_options)?;
+    report_rejected_lines(stream.fleet(), options.rejected_lines_path.as_deref())?;
+    report_orphaned_chargers(&stream.orphaned_chargers());
+    if options.orphans_as_error {
+        stream.check_orphaned_chargers()?;
+    }
+    stream.finish()
 }
 
-/// Takes in a tuple of station-charger map, charger-uptime map, 
-/// and station insertion order, to compute availability percentage 
-/// for each station
-/// 
+/// Validates the input files without computing uptime, and prints the
+/// findings and a summary to `stdout`.
+///
 /// ### Input:
-/// - `station_charger_map`: A map of Station ID to IDs of chargers at the station
-/// - `charger_uptime_map`: A map of Charger ID to `TimeRange` structs for the charger
+/// - `args`: CLI params after the binary, starting with `validate`.
 ///
-/// ### Output: This function does not return anything.
-fn compute_availability( station_charger_map: HashMap<u32, HashSet<u32>>,
-                         mut charger_uptime_map: HashMap<u32, Vec<TimeRange>>)
-                         -> Result<Vec<(u32, u8)>, Error> {
-
-    let mut station_availability : Vec<(u32, u8)> = Vec::new();
-    for (station_id, chargers) in station_charger_map {
-
-        // Gathering all charger reportings of a station
-        let mut station_reported_time: Vec<TimeRange> = Vec::new();
-        for charger in chargers {
-            let charger_times = charger_uptime_map.get_mut(&charger);
-            if charger_times.is_none() {
-                continue;
-            }
-
-            let charger_times_sorted: &mut Vec<TimeRange> = charger_times.unwrap();
-            charger_times_sorted.sort_by(|a,b|a.cmp(&b));
-            let charger_times_combined = charger_times_combine(charger_times_sorted);
-            if let Ok(charger_reports) = charger_times_combined {
-                for charger_time in charger_reports {
-                    station_reported_time.push(charger_time.clone());
+/// ### Output:
+/// - `Result<(), UptimeError>`: `UptimeError::ValidationFailed` if errors were
+///   found, or warnings with `--deny-warnings`.
+fn run_validate(args: &[String]) -> Result<(), UptimeError> {
+
+    let mut input_paths: Vec<String> = Vec::new();
+    let mut format = OutputFormat::default();
+    let mut deny_warnings = false;
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--deny-warnings" => deny_warnings = true,
+            "--format" => {
+                let format_name = option_value(arg, args_iter.next())?;
+                format = format_name.parse::<OutputFormat>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+                if format == OutputFormat::Ndjson {
+                    return Err(UptimeError::InvalidArguments { message: "The validate command supports the text and json formats.".to_string() });
                 }
-            } else {
-                return Err(Error::new(ErrorKind::InvalidData, format!("Conflicting availability entires found for charger {}", charger)));
-            }
-        }
-
-        if station_reported_time.len()==0 {
-            // No charger reported in from this station.
-            // Uncomment this next line to display station as 0 percent availability
-            // station_availability.push((station_id, 0u8));
-            continue;
+            },
+            flag if flag.starts_with("--") => {
+                return Err(UptimeError::InvalidArguments { message: format!("Unknown option '{}'.", flag) });
+            },
+            path => input_paths.push(path.to_string()),
         }
-
-        // Sort in ascending order of 'from time' of availability report
-        station_reported_time.sort_by(|a,b| a.cmp(&b));
-
-        // Guaranteed to have at least one reported time at this point.
-        // Initializing to the first report.
-        let first_report = station_reported_time.first().unwrap();
-        let first_reported_time = first_report.from;
-        let mut last_reported_time = first_report.to;
-        let mut available_time: u64 = 
-            if first_report.up {
-                first_report.to -first_report.from
-            } else { 
-                0
-            };
-        let mut reported_till_time: u64 = last_reported_time;
-
-        // Starting from the second report
-        for i in 1..station_reported_time.len() {
-            let charger_time: &TimeRange = &station_reported_time[i];
-
-            // Keep track of ending of reported time
-            if last_reported_time<charger_time.to {
-                last_reported_time = charger_time.to;
-            }
-
-            if !charger_time.up {
-                // charger is unavailable
-                continue;
-            }
-            // charger_time window already covered in previous window
-            if reported_till_time >= charger_time.to {
-                continue;
-            }
-            // charger_time window partial overlap in previous window
-            if reported_till_time >=charger_time.from {
-                available_time += charger_time.to-reported_till_time;
-            } else {
-                available_time += charger_time.to - charger_time.from;
-            }
-            reported_till_time = charger_time.to;
-        }
-        let mut total_time: u64 = last_reported_time - first_reported_time;
-
-        if total_time>10000 {
-            // Dividing by 100 to avoid overflow by multiplication
-            total_time/=100;
-        } else {
-            // If total time is small, available time will be smaller
-            // Multiplying will not cause overflow
-            available_time*=100;
-        }
-
-        // Total time is guaranteed to not be zero here.
-        let availability_percent: u64 = available_time/total_time;
-        station_availability.push((station_id, availability_percent as u8));
     }
-    return Ok(station_availability);
-}
+    if input_paths.is_empty() {
+        return Err(UptimeError::InvalidArguments {
+            message: "Missing file path parameter. Please pass a relative file path.".to_string(),
+        });
+    }
 
-fn charger_times_combine(charger_times: &Vec<TimeRange>) -> Result<Vec<TimeRange>, Error> {
-    let mut condensed_times: Vec<TimeRange> = Vec::new();
-    let first_report = charger_times.first().unwrap();
-    let mut curr_report = first_report.clone();
-    for i in 1..charger_times.len() {
-        let curr = &charger_times[i];
-        if curr.from<curr_report.to {
-            if curr.up == curr_report.up {
-                curr_report.to = curr.to;
-            } else {
-                return Err(Error::new(ErrorKind::InvalidData, "Conflicting charger reports. Overlapping windows for the same charger."));
-            }
-        } else {
-            condensed_times.push(curr_report.clone());
-            drop(curr_report);
-            curr_report = curr.clone();
-        }
+    let mut validator = Validator::new();
+    for file_path in expand_input_paths(&input_paths)? {
+        validator.read_file(&file_path)
+            .map_err(|uptime_error| uptime_error.with_file(&file_path))?;
     }
-    condensed_times.push(curr_report);
-    Ok(condensed_times)
-}
+    let report = validator.finish();
 
-/// Takes in a string reference to a file path, and returns a tuple of 
-/// station-charger map, charger-uptime map, and station insertion order
-/// ### Input: 
-/// - `file_path`: A string reference to file path
-/// 
-/// ### Output: A tuple consisting of
-/// - `station_charger_map`: A map of Station ID to IDs of chargers at the station
-/// - `charger_uptime_map`: A map of Charger ID to `TimeRange` structs for the charger
-fn construct_maps(file_path: &str) -> Result<( HashMap<u32, HashSet<u32>>, 
-                                        HashMap<u32, Vec<TimeRange>>), Error> {
-
-    let mut currently_reading: InputKind = InputKind::None;
-    let mut station_charger_map: HashMap<u32, HashSet<u32>> = HashMap::new();
-    let mut charger_uptime_map: HashMap<u32, Vec<TimeRange>> = HashMap::new();
-    let mut charger_station_map: HashMap<u32, u32> = HashMap::new();
-    let lines_iterator = read_lines(&file_path);
-    if let Err(lines_iterator_error) = lines_iterator {
-        return Err(lines_iterator_error);
+    let mut stdout = io::stdout().lock();
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut stdout, &report).map_err(io::Error::from)?;
+    } else {
+        write!(stdout, "{}", report)?;
     }
-    let lines = lines_iterator.unwrap();
-    for wrapped_line in lines {
-        if let Err(line_error) = wrapped_line {
-            return Err(line_error);
-        }
-        let l = wrapped_line.unwrap();
-        match l.trim() {
-            "" => {},
-            "[Stations]" => currently_reading = InputKind::Station,
-            "[Charger Availability Reports]" => currently_reading = InputKind::ChargerAvailability,
-            trimmed_l => {
-                match currently_reading {
-                    InputKind::None => {
-                        return Err(Error::new(ErrorKind::InvalidData, "Invalid file format. Unable to read file."));
-                    },
-                    InputKind::Station => {
-                        let station_parse_result = parse_station(trimmed_l);
-                        if let Err(station_parse_error) = station_parse_result {
-                            return Err(station_parse_error);
-                        }
-                        let (station_id, chargers) = station_parse_result.unwrap();
-                        if !station_charger_map.contains_key(&station_id) {
-                            station_charger_map.insert(station_id, HashSet::new());
-                        }
-                        for charger in &chargers {
-                            if let Some(mapped_station) = charger_station_map.get(&charger) {
-                                if *mapped_station!=station_id {
-                                    return Err(Error::new(ErrorKind::InvalidData, format!("Invalid data. The Charger ID '{}' is mapped to multiple Station IDs({}, and {})", charger, station_id, mapped_station)));
-                                }
-                            }
-                            charger_station_map.insert(*charger, station_id);
-                        }
-                        let charger_set: &mut HashSet<u32> = station_charger_map.get_mut(&station_id).unwrap();
-                        charger_set.extend(chargers);
-                    },
-                    InputKind::ChargerAvailability => {
-                        let charger_parse_result = parse_charger_availability(trimmed_l);
-                        if let Err(charger_parse_error) = charger_parse_result {
-                            return Err(charger_parse_error);
-                        }
-                        let (charger_id, time_range) = charger_parse_result.unwrap();
-                        if !charger_uptime_map.contains_key(&charger_id) {
-                            let mut uptime_ranges: Vec<TimeRange> = Vec::new();
-                            uptime_ranges.push(time_range);
-                            charger_uptime_map.insert(charger_id, uptime_ranges);
-                        } else {
-                            let uptime_ranges: &mut Vec<TimeRange> = charger_uptime_map.get_mut(&charger_id).unwrap();
-                            uptime_ranges.push(time_range);
-                        }
-                    },
-                }
-            }
-        }
+    writeln!(stdout)?;
+    stdout.flush()?;
+
+    if report.errors>0 || (deny_warnings && report.warnings>0) {
+        return Err(UptimeError::ValidationFailed { errors: report.errors, warnings: report.warnings });
     }
-    return Ok((station_charger_map, charger_uptime_map))
+    Ok(())
 }
 
-/// Parses a line of station info and returns it wrapped in a `Result()`.
-/// ### Input :
-/// - `line`: A string reference containing station id and ids of chargers at a station.
-/// Expected format of `line`:
-/// <Station ID 1> <Charger ID 1> <Charger ID 2> ... <Charger ID n>
+/// Writes synthetic input to `stdout`, or to the file set with `--output`.
+///
+/// ### Input:
+/// - `args`: CLI params after the binary, starting with `generate`.
 ///
 /// ### Output:
-/// - `Result<(Station ID, Vec<Charger IDs>), Error>`: A tuple of station id and a vector 
-/// of charger ids wrapped in `Ok()` if successful and `Error` in case of error. 
-fn parse_station(line: &str) -> Result<(u32, Vec<u32>), Error> {
-
-    let re = Regex::new(r"\s+").unwrap();
-    let mut splits: Vec<&str> = re.split(line).collect();
-    if splits.len()==0 {
-        return Err(Error::new(ErrorKind::InvalidData, format!("Could not parse station: \n'{}'", line)));
-    }
-    let station_id_str = splits.swap_remove(0);
-    let station_id_wrapped = station_id_str.parse::<u32>();
-    if station_id_wrapped.is_err() {
-        return Err(Error::new(ErrorKind::InvalidData, format!("Invalid station ID: '{}'", station_id_str)));
-    }
-    let station_id = station_id_wrapped.unwrap();
-    let mut chargers: Vec<u32> = Vec::new();
-
-    while splits.len()>0 {
-        let charger_id_wrapped = splits.pop().unwrap().parse::<u32>();
-        if charger_id_wrapped.is_err() {
-            return Err(Error::new(ErrorKind::InvalidData, format!("Invalid station entry for Station ID: {}.\nCould not parse charger ID.", station_id)));
+/// - `Result<(), UptimeError>`: `UptimeError` in case of invalid options or
+///   failure to write.
+fn run_generate(args: &[String]) -> Result<(), UptimeError> {
+
+    let mut options = GenerateOptions::default();
+    let mut output_path: Option<String> = None;
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--stations" => options.stations = number_value(arg, args_iter.next())?,
+            "--chargers-per-station" => options.chargers_per_station = number_value(arg, args_iter.next())?,
+            "--reports-per-charger" => options.reports_per_charger = number_value(arg, args_iter.next())?,
+            "--report-duration" => options.report_duration = number_value(arg, args_iter.next())?,
+            "--start" => options.start = nanos_value(arg, args_iter.next())?,
+            "--outage-rate" => options.outage_rate = rate_value(arg, args_iter.next())?,
+            "--overlap-rate" => options.overlap_rate = rate_value(arg, args_iter.next())?,
+            "--conflict-rate" => options.conflict_rate = rate_value(arg, args_iter.next())?,
+            "--shuffle" => options.shuffle = true,
+            "--seed" => options.seed = number_value(arg, args_iter.next())?,
+            "--output" => output_path = Some(option_value(arg, args_iter.next())?),
+            unknown => {
+                return Err(UptimeError::InvalidArguments { message: format!("Unknown option '{}'.", unknown) });
+            },
         }
-        chargers.push(charger_id_wrapped.unwrap());
     }
-    Ok((station_id, chargers))
 
+    let writer: Box<dyn Write> = match &output_path {
+        Some(output_path) => Box::new(File::create(output_path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut writer = BufWriter::new(writer);
+    generate(&mut writer, &options).map_err(|io_error| match io_error.kind() {
+        io::ErrorKind::InvalidInput => UptimeError::InvalidArguments { message: io_error.to_string() },
+        _ => UptimeError::Io(io_error),
+    })?;
+    writer.flush()?;
+    Ok(())
 }
 
-/// Parses a line of charger availability info and returns it wrapped in a `Result()`.
-/// ### Input :
-/// - `line`: A string reference containing charger id, start time, end time,
-/// and up/down status of charger.
-/// Expected format of `line`:
-/// <Charger ID 1> <start time nanos> <end time nanos> <up (true/false)>
+/// Reads the input paths and flags from CLI params. Any number of input paths
+/// can be passed, including glob patterns and `-` for `stdin`. If neither an
+/// input path nor a CSV file is found, or a flag is not recognized, returns an error.
+///
+/// ### Input:
+/// - `args`: CLI params, including the path to the binary as the first one.
 ///
 /// ### Output:
-/// - `Result<(Charger ID, TimeRange struct), Error>`: A tuple of station id and a struct 
-/// `TimeRange` wrapped in `Ok()` if successful and `Error` in case of error. 
-/// The `TimeRange` struct contains parsed start time, end time, and up/down status of charger. 
-fn parse_charger_availability(line: &str) -> Result<(u32, TimeRange), Error> {
-    let re = Regex::new(r"(?<charger_id>\d+)\s+(?<start_time>\d+)\s+(?<end_time>\d+)\s*(?<up_status>\w*)").unwrap();
-    let captures_wrapped = re.captures(line);
-    if captures_wrapped.is_none() {
-        return Err(Error::new(ErrorKind::InvalidData, "Could not parse charger availability entry. Please check the input file."));
-    }
-    let captures = captures_wrapped.unwrap();
-    let charger_id_wrapped = captures["charger_id"].parse::<u32>();
-    if charger_id_wrapped.is_err() {
-        return Err(Error::new(ErrorKind::InvalidData, "Invalid charger availability entry.\nCould not parse charger ID."));
-    }
-    let charger_id = charger_id_wrapped.unwrap();
-    let start_time_wrapped = captures["start_time"].parse::<u64>();
-    if start_time_wrapped.is_err() {
-        return Err(Error::new(ErrorKind::InvalidData, format!("Invalid charger availability entry.\nCould not parse start time for charger ID: {}.", charger_id)));
-    }
-    let end_time_wrapped = captures["end_time"].parse::<u64>();
-    if end_time_wrapped.is_err() {
-        return Err(Error::new(ErrorKind::InvalidData, format!("Invalid charger availability entry.\nCould not parse end time for charger ID: {}.", charger_id)));
-    }
-    // Note: Any input for up status that's not 'true' or 'True' will be considered as false.
-    let time_range = TimeRange {
-        from: start_time_wrapped.unwrap(),
-        to: end_time_wrapped.unwrap(),
-        up: match &captures["up_status"] {
-            "true" | "True" => true,
-            "false" | "False" => false,
-            _ => return Err(Error::new(ErrorKind::InvalidData, format!("Invalid charger availability entry.\nMissing charger up status(true/false) in entry:\n{}", line))),
-        },
-    };
-    if time_range.from>time_range.to {
-        return Err(Error::new(ErrorKind::InvalidData, format!("Invalid charger availability entry for charger ID {}!\nAvailability from is after availability to.", charger_id)));
-    }
-    Ok((charger_id, time_range))
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn parse_station_test_valid() {
-        let station_string = "1 1001 1002";
-        let chargers_vec: Vec<u32> = vec![1001, 1002];
-        let station_id: u32 = 1;
-        let parse_output = parse_station(&station_string);
-        assert!(!parse_output.is_err());
-        let (station_id_parsed, chargers_parsed) = parse_output.unwrap();
-        assert_eq!(station_id, station_id_parsed); 
-        assert_eq!(chargers_vec, chargers_parsed);
-    }
+/// - `Result<Options, UptimeError>`: The options wrapped in `Ok()` if successful
+///   and `UptimeError::InvalidArguments` in case of error.
+fn parse_args(args: &[String]) -> Result<Options, UptimeError> {
+
+    let mut input_paths: Vec<String> = Vec::new();
+    let mut stations_csv_path: Option<String> = None;
+    let mut reports_csv_path: Option<String> = None;
+    let mut csv_options = CsvOptions::default();
+    let mut read_options = ReadOptions::default();
+    let mut rejected_lines_path: Option<String> = None;
+    let mut compute_options = ComputeOptions::default();
+    let mut per_charger = false;
+    let mut per_connector = false;
+    let mut excluded_time = false;
+    let mut orphans_as_error = false;
+    let mut stream_order: Option<StreamOrder> = None;
+    let mut format = OutputFormat::default();
+    let mut window_from: Option<u64> = None;
+    let mut window_to: Option<u64> = None;
+    let mut unreported: Option<UnreportedTime> = None;
 
-    #[test]
-    fn parse_station_wrong_id() {
-        let station_string = "A 1001 1002";
-        let parse_output = parse_station(&station_string);
-        assert!(parse_output.is_err());
-        if let Err(parse_error) = parse_output {
-            assert_eq!(parse_error.to_string(), "Invalid station ID: 'A'"); 
-        } else {
-            panic!("Parsing was successful!");
+    // The path to the target binary will be passed as the first argument.
+    // Hence skipping it here
+    let mut args_iter = args.iter().skip(1);
+    while let Some(arg) = args_iter.next() {
+        match arg.as_str() {
+            "--all-errors" => read_options.collect_errors = true,
+            "--lenient" => read_options.lenient = true,
+            "--per-charger" => per_charger = true,
+            "--per-connector" => per_connector = true,
+            "--excluded-time" => excluded_time = true,
+            "--orphans-as-error" => orphans_as_error = true,
+            "--stations-csv" => stations_csv_path = Some(option_value(arg, args_iter.next())?),
+            "--reports-csv" => reports_csv_path = Some(option_value(arg, args_iter.next())?),
+            "--csv-delimiter" => {
+                let delimiter = option_value(arg, args_iter.next())?;
+                if delimiter.len()!=1 {
+                    return Err(UptimeError::InvalidArguments { message: format!("Invalid CSV delimiter '{}'. Expected a single character.", delimiter) });
+                }
+                csv_options.delimiter = delimiter.as_bytes()[0];
+            },
+            "--csv-column" => {
+                let mapping = option_value(arg, args_iter.next())?;
+                csv_options.columns.set_from_mapping(&mapping)
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--format" => {
+                let format_name = option_value(arg, args_iter.next())?;
+                format = format_name.parse::<OutputFormat>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--rejected-lines" => rejected_lines_path = Some(option_value(arg, args_iter.next())?),
+            "--from" => window_from = Some(nanos_value(arg, args_iter.next())?),
+            "--to" => window_to = Some(nanos_value(arg, args_iter.next())?),
+            "--unreported-time" => {
+                let unreported_name = option_value(arg, args_iter.next())?;
+                unreported = Some(unreported_name.parse::<UnreportedTime>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?);
+            },
+            "--bucket" => {
+                let bucket_name = option_value(arg, args_iter.next())?;
+                compute_options.bucket_width = Some(bucket_name.parse::<BucketWidth>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?);
+            },
+            "--precision" => {
+                let precision = option_value(arg, args_iter.next())?;
+                compute_options.percent_format.precision = match precision.parse::<u8>() {
+                    Ok(precision) if precision<=MAX_PRECISION => precision,
+                    _ => return Err(UptimeError::InvalidArguments {
+                        message: format!("Invalid precision '{}'. Expected a number of decimal places from 0 to {}.", precision, MAX_PRECISION),
+                    }),
+                };
+            },
+            "--rounding" => {
+                let rounding_name = option_value(arg, args_iter.next())?;
+                compute_options.percent_format.rounding = rounding_name.parse::<Rounding>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--no-data" => {
+                let policy_name = option_value(arg, args_iter.next())?;
+                compute_options.no_data_policy = policy_name.parse::<NoDataPolicy>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--station-metric" => {
+                let metric_name = option_value(arg, args_iter.next())?;
+                compute_options.station_metric = metric_name.parse::<StationMetric>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--connector-rollup" => {
+                let rollup_name = option_value(arg, args_iter.next())?;
+                compute_options.connector_rollup = rollup_name.parse::<ConnectorRollup>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--up-statuses" => {
+                let status_names = option_value(arg, args_iter.next())?;
+                compute_options.up_statuses = status_names.parse::<UpStatuses>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            "--jobs" => {
+                let jobs = option_value(arg, args_iter.next())?;
+                compute_options.jobs = match jobs.parse::<usize>() {
+                    Ok(jobs) if jobs>0 => jobs,
+                    _ => return Err(UptimeError::InvalidArguments {
+                        message: format!("Invalid number of jobs '{}'. Expected a number of threads of at least 1.", jobs),
+                    }),
+                };
+            },
+            "--stream" => {
+                let order_name = option_value(arg, args_iter.next())?;
+                stream_order = Some(order_name.parse::<StreamOrder>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?);
+            },
+            "--conflict-policy" => {
+                let policy_name = option_value(arg, args_iter.next())?;
+                compute_options.conflict_policy = policy_name.parse::<ConflictPolicy>()
+                    .map_err(|message| UptimeError::InvalidArguments { message })?;
+            },
+            flag if flag.starts_with("--") => {
+                return Err(UptimeError::InvalidArguments { message: format!("Unknown option '{}'.", flag) });
+            },
+            path => input_paths.push(path.to_string()),
         }
     }
 
-    #[test]
-    fn parse_station_missing_chargers() {
-        let station_string = "1";
-        let chargers_vec: Vec<u32> = Vec::new();
-        let station_id: u32 = 1;
-        let parse_output = parse_station(&station_string);
-        assert!(parse_output.is_ok());
-        let (station_id_parsed, chargers_parsed) = parse_output.unwrap();
-        assert_eq!(station_id, station_id_parsed); 
-        assert_eq!(chargers_vec, chargers_parsed);
-    }
-
-    #[test]
-    fn parse_station_empty() {
-        let station_string = "";
-        let parse_output = parse_station(&station_string);
-        assert!(parse_output.is_err());
-        if let Err(parse_error) = parse_output {
-            assert_eq!(parse_error.to_string(), "Invalid station ID: ''"); 
-        } else {
-            panic!("Parsing was successful!");
-        }
-    }
+    if input_paths.is_empty() && stations_csv_path.is_none() && reports_csv_path.is_none() {
+        return Err(UptimeError::InvalidArguments {
+            message: "Missing file path parameter. Please pass a relative file path.".to_string(),
+        });
+    }
+    compute_options.window = match (window_from, window_to) {
+        (Some(from), Some(to)) => Some(ReportingWindow::new(from, to, unreported.unwrap_or_default())
+            .map_err(|message| UptimeError::InvalidArguments { message })?),
+        (None, None) if unreported.is_none() => None,
+        (None, None) => return Err(UptimeError::InvalidArguments {
+            message: "Option '--unreported-time' needs a reporting window set with '--from' and '--to'.".to_string(),
+        }),
+        _ => return Err(UptimeError::InvalidArguments {
+            message: "A reporting window needs both '--from' and '--to'.".to_string(),
+        }),
+    };
+    if stream_order.is_some() && (stations_csv_path.is_some() || reports_csv_path.is_some()) {
+        return Err(UptimeError::InvalidArguments {
+            message: "Option '--stream' reads text input, and can't be used with '--stations-csv' or '--reports-csv'.".to_string(),
+        });
+    }
+    if stream_order.is_some() && compute_options.jobs>1 {
+        return Err(UptimeError::InvalidArguments {
+            message: "Options '--stream' and '--jobs' can't be used together.".to_string(),
+        });
+    }
+    if stream_order.is_some() && compute_options.bucket_width.is_some() {
+        return Err(UptimeError::InvalidArguments {
+            message: "Options '--stream' and '--bucket' can't be used together.".to_string(),
+        });
+    }
+    if stream_order.is_some() && compute_options.station_metric != StationMetric::AnyUp {
+        return Err(UptimeError::InvalidArguments {
+            message: "Options '--stream' and '--station-metric' can't be used together.".to_string(),
+        });
+    }
+    let record_flags = [
+        (per_charger, RecordKind::Chargers),
+        (per_connector, RecordKind::Connectors),
+        (compute_options.bucket_width.is_some(), RecordKind::Buckets),
+        (excluded_time, RecordKind::Exclusions),
+    ];
+    let mut selected_records = record_flags.iter().filter(|(selected, _)| *selected);
+    let records = match (selected_records.next(), selected_records.next()) {
+        (None, _) => RecordKind::Stations,
+        (Some((_, records)), None) => *records,
+        (Some(_), Some(_)) => return Err(UptimeError::InvalidArguments {
+            message: "Only one of '--per-charger', '--per-connector', '--bucket' and '--excluded-time' can be used at a time.".to_string(),
+        }),
+    };
+    Ok(Options {
+        input_paths,
+        stations_csv_path,
+        reports_csv_path,
+        csv_options,
+        read_options,
+        rejected_lines_path,
+        compute_options,
+        orphans_as_error,
+        stream_order,
+        records,
+        format,
+    })
+}
 
-    #[test]
-    fn parse_station_invalid_charger() {
-        let station_string = "1 4294967296";
-        let parse_output = parse_station(&station_string);
-        assert!(parse_output.is_err());
-        if let Err(parse_error) = parse_output {
-            assert_eq!(parse_error.to_string(), "Invalid station entry for Station ID: 1.\nCould not parse charger ID."); 
-        } else {
-            panic!("Parsing was successful!");
-        }
+/// Returns the value following an option, or an error if it's missing.
+fn option_value(option: &str, value: Option<&String>) -> Result<String, UptimeError> {
+    match value {
+        Some(value) => Ok(value.clone()),
+        None => Err(UptimeError::InvalidArguments { message: format!("Missing value for option '{}'.", option) }),
     }
+}
 
-    #[test]
-    fn parse_station_max_charger_id() {
-        let station_string = "1 4294967295";
-        let chargers_vec: Vec<u32> = vec![4294967295];
-        let station_id: u32 = 1;
-        let parse_output = parse_station(&station_string);
-        assert!(parse_output.is_ok());
-        let (station_id_parsed, chargers_parsed) = parse_output.unwrap();
-        assert_eq!(station_id, station_id_parsed); 
-        assert_eq!(chargers_vec, chargers_parsed);
-    }
+/// Returns the value following an option as a whole number, or an error if
+/// it's missing or not a number.
+fn number_value<T: std::str::FromStr>(option: &str, value: Option<&String>) -> Result<T, UptimeError> {
+    let value = option_value(option, value)?;
+    value.parse::<T>().map_err(|_| UptimeError::InvalidArguments {
+        message: format!("Invalid value '{}' for option '{}'. Expected a whole number.", value, option),
+    })
+}
 
-    #[test]
-    fn parse_station_neg_charger_id() {
-        let station_string = "1 -1";
-        let parse_output = parse_station(&station_string);
-        assert!(parse_output.is_err());
-        if let Err(parse_error) = parse_output {
-            assert_eq!(parse_error.to_string(), "Invalid station entry for Station ID: 1.\nCould not parse charger ID."); 
-        } else {
-            panic!("Parsing was successful!");
-        }
+/// Returns the value following an option as a probability from 0 to 1, or an
+/// error if it's missing or out of range.
+fn rate_value(option: &str, value: Option<&String>) -> Result<f64, UptimeError> {
+    let value = option_value(option, value)?;
+    match value.parse::<f64>() {
+        Ok(rate) if (0.0..=1.0).contains(&rate) => Ok(rate),
+        _ => Err(UptimeError::InvalidArguments {
+            message: format!("Invalid value '{}' for option '{}'. Expected a rate from 0 to 1.", value, option),
+        }),
     }
+}
 
-    #[test]
-    fn parse_charger_valid() {
-        let charger_string = "1 1000 10000 true";
-        let charger_id: u32 = 1;
-        let time_range = TimeRange {
-            from: 1000,
-            to: 10000,
-            up: true,
-        };
-        let parse_output = parse_charger_availability(&charger_string);
-        assert!(parse_output.is_ok());
-        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
-        assert_eq!(charger_id, charger_id_parsed); 
-        assert_eq!(time_range, time_range_parsed);
-    }
+/// Returns the value following an option as a timestamp in nanoseconds, or an
+/// error if it's missing or not a number.
+fn nanos_value(option: &str, value: Option<&String>) -> Result<u64, UptimeError> {
+    let value = option_value(option, value)?;
+    value.parse::<u64>().map_err(|_| UptimeError::InvalidArguments {
+        message: format!("Invalid value '{}' for option '{}'. Expected a timestamp in nanoseconds.", value, option),
+    })
+}
 
+/// Prints each charger that reported availability but belongs to no station
+/// to `stderr`, with its number of reports and total reported time.
+fn report_orphaned_chargers(orphaned_chargers: &[OrphanedCharger]) {
 
-    #[test]
-    fn parse_charger_invalid_id() {
-        let charger_string = "A 1000 10000 true";
-        let parse_output = parse_charger_availability(&charger_string);
-        assert!(parse_output.is_err());
-        if let Err(parse_error) = parse_output {
-            assert_eq!(parse_error.to_string(), "Could not parse charger availability entry. Please check the input file."); 
-        } else {
-            panic!("Parsing was successful!");
-        }
+    if orphaned_chargers.is_empty() {
+        return;
     }
-
-    #[test]
-    fn parse_charger_up_false() {
-        let charger_string = "1 1000 10000 false";
-        let charger_id: u32 = 1;
-        let time_range = TimeRange {
-            from: 1000,
-            to: 10000,
-            up: false,
-        };
-        let parse_output = parse_charger_availability(&charger_string);
-        assert!(parse_output.is_ok());
-        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
-        assert_eq!(charger_id, charger_id_parsed); 
-        assert_eq!(time_range, time_range_parsed);
+    eprintln!("WARNING: {} charger(s) report availability but belong to no station:", orphaned_chargers.len());
+    for orphaned_charger in orphaned_chargers {
+        eprintln!("  {}: {} report(s), {} ns reported",
+            orphaned_charger.charger_id, orphaned_charger.report_count, orphaned_charger.reported_nanos);
     }
+}
 
-    #[test]
-    fn parse_charger_up_missing_false() {
-        let charger_string = "1 1000 10000";
-        let charger_id: u32 = 1;
-        let time_range = TimeRange {
-            from: 1000,
-            to: 10000,
-            up: false,
-        };
-        let parse_output = parse_charger_availability(&charger_string);
-        assert!(parse_output.is_ok());
-        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
-        assert_eq!(charger_id, charger_id_parsed); 
-        assert_eq!(time_range, time_range_parsed);
-    }
+/// Prints a summary of lines skipped in lenient mode to `stderr`, with the
+/// number of lines skipped for each kind of error. If a path is given, also
+/// writes each skipped line to that file as `<file>:<line>: <kind>: <raw line>`.
+fn report_rejected_lines(fleet: &Fleet, rejected_lines_path: Option<&str>) -> Result<(), UptimeError> {
 
-    #[test]
-    fn parse_charger_up_true_pascal() {
-        let charger_string = "1 1000 10000 True";
-        let charger_id: u32 = 1;
-        let time_range = TimeRange {
-            from: 1000,
-            to: 10000,
-            up: true,
-        };
-        let parse_output = parse_charger_availability(&charger_string);
-        assert!(parse_output.is_ok());
-        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
-        assert_eq!(charger_id, charger_id_parsed); 
-        assert_eq!(time_range, time_range_parsed);
+    let rejected_lines = fleet.rejected_lines();
+    if !rejected_lines.is_empty() {
+        eprintln!("WARNING: Skipped {} invalid line(s):", rejected_lines.len());
+        for (category, count) in fleet.rejected_line_counts() {
+            eprintln!("  {}: {}", category, count);
+        }
     }
 
-    #[test]
-    fn parse_charger_before_gt_after() {
-        let charger_string = "1 10000 1000 true";
-        let parse_output = parse_charger_availability(&charger_string);
-        assert!(parse_output.is_err());
-        if let Err(parse_error) = parse_output {
-            assert_eq!(parse_error.to_string(), "Invalid charger availability entry for charger ID 1!\nAvailability from is after availability to."); 
-        } else {
-            panic!("Parsing was successful!");
+    if let Some(path) = rejected_lines_path {
+        let mut writer = BufWriter::new(File::create(path)?);
+        for rejected in rejected_lines {
+            writeln!(writer, "{}:{}: {}: {}",
+                rejected.location.file.as_deref().unwrap_or("<input>"),
+                rejected.location.line,
+                rejected.category,
+                rejected.location.source_line)?;
         }
+        writer.flush()?;
     }
-}
\ No newline at end of file
+    Ok(())
+}
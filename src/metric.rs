@@ -0,0 +1,196 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::time_range::TimeRange;
+
+/// How the up time of a station is computed from the up time of its chargers.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StationMetric {
+    /// The station is up while any of its chargers is up.
+    #[default]
+    AnyUp,
+    /// The station is up only while all of its chargers are up.
+    AllUp,
+    /// The station is up while at least this many of its chargers are up.
+    AtLeast(usize),
+    /// The station's up time is the mean fraction of its chargers that are
+    /// up, over time. A station of 4 chargers with one of them down all the
+    /// time is 75% up.
+    PortWeighted
+}
+
+impl StationMetric {
+
+    /// Up time of a station within `[from, to)`, from the reports of its chargers.
+    ///
+    /// ### Input:
+    /// - `chargers`: The chargers at the station. Chargers without any windows
+    ///   never reported, and are left out like unreported time.
+    /// - `from`: Start of the time to compute up time over
+    /// - `to`: End of the time to compute up time over
+    ///
+    /// ### Output:
+    /// - `u64`: Up time in nanoseconds. With `StationMetric::PortWeighted`,
+    ///   it's the mean up time of the chargers, rounded down.
+    pub(crate) fn up_time(&self, chargers: &[ChargerTimes], from: u64, to: u64) -> u64 {
+        let reporting: Vec<&ChargerTimes> = chargers.iter().filter(|charger| !charger.times.is_empty()).collect();
+        let up_chargers = match self {
+            StationMetric::AnyUp => 1,
+            StationMetric::AllUp => reporting.len(),
+            StationMetric::AtLeast(up_chargers) => *up_chargers,
+            StationMetric::PortWeighted => return port_weighted_up_time(&reporting, from, to),
+        };
+
+        // Changes in the number of chargers up, swept in order of time
+        let mut changes: Vec<(u64, isize)> = Vec::new();
+        for (up_from, up_to) in reporting.iter().flat_map(|charger| charger.up_windows_within(from, to)) {
+            changes.push((up_from, 1));
+            changes.push((up_to, -1));
+        }
+        changes.sort_by_key(|(time, _)| *time);
+        let mut up_time: u64 = 0;
+        let mut up_count: isize = 0;
+        let mut previous_time: u64 = 0;
+        for (time, change) in changes {
+            if up_count > 0 && up_count as usize >= up_chargers {
+                up_time += time - previous_time;
+            }
+            up_count += change;
+            previous_time = time;
+        }
+        up_time
+    }
+}
+
+/// Mean up time of chargers within `[from, to)`, rounded down.
+fn port_weighted_up_time(chargers: &[&ChargerTimes], from: u64, to: u64) -> u64 {
+    let charger_up_time: u128 = chargers.iter()
+        .map(|charger| u128::from(charger.up_time_within(from, to)))
+        .sum();
+    (charger_up_time / chargers.len().max(1) as u128) as u64
+}
+
+/// Power-weighted up time of a station within `[from, to)`, computed
+/// alongside the station metric.
+///
+/// ### Input:
+/// - `chargers`: The chargers at the station. Chargers without any windows
+///   never reported, and are left out like unreported time.
+/// - `from`: Start of the time to compute up time over
+/// - `to`: End of the time to compute up time over
+///
+/// ### Output:
+/// - `Option<u64>`: Mean up time of the chargers weighted by their power
+///   ratings, rounded down. `None` if the station is unrated: a charger that
+///   reported has no power rating, or no charger reported. An unrated charger
+///   isn't given a weight, since any weight would be a guess.
+pub(crate) fn power_weighted_up_time(chargers: &[ChargerTimes], from: u64, to: u64) -> Option<u64> {
+    let mut total_power: f64 = 0.0;
+    let mut weighted_up_time: f64 = 0.0;
+    for charger in chargers.iter().filter(|charger| !charger.times.is_empty()) {
+        let power_kw = charger.power_kw?;
+        total_power += power_kw;
+        weighted_up_time += power_kw * charger.up_time_within(from, to) as f64;
+    }
+    (total_power > 0.0).then(|| (weighted_up_time / total_power) as u64)
+}
+
+/// Reports of a charger at a station, for computing station up time.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub(crate) struct ChargerTimes {
+    /// Weight of the charger in power-weighted up time: its power rating in
+    /// kW, if it has one.
+    pub power_kw: Option<f64>,
+    /// Sorted, non-overlapping windows of the charger, like combined reports.
+    pub times: Vec<TimeRange>
+}
+
+impl ChargerTimes {
+
+    /// Parts of the up windows inside `[from, to)`, in order.
+    fn up_windows_within(&self, from: u64, to: u64) -> impl Iterator<Item = (u64, u64)> + '_ {
+        // Windows don't overlap, so they end in order too
+        let first = self.times.partition_point(|time_range| time_range.to <= from);
+        self.times[first..].iter()
+            .take_while(move |time_range| time_range.from < to)
+            .filter(|time_range| time_range.up)
+            .map(move |time_range| (time_range.from.max(from), time_range.to.min(to)))
+            .filter(|(up_from, up_to)| up_from < up_to)
+    }
+
+    fn up_time_within(&self, from: u64, to: u64) -> u64 {
+        self.up_windows_within(from, to).map(|(up_from, up_to)| up_to - up_from).sum()
+    }
+}
+
+impl fmt::Display for StationMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StationMetric::AnyUp => write!(f, "any-up"),
+            StationMetric::AllUp => write!(f, "all-up"),
+            StationMetric::AtLeast(up_chargers) => write!(f, "at-least-{}", up_chargers),
+            StationMetric::PortWeighted => write!(f, "port-weighted"),
+        }
+    }
+}
+
+impl FromStr for StationMetric {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<StationMetric, String> {
+        match name {
+            "any-up" => Ok(StationMetric::AnyUp),
+            "all-up" => Ok(StationMetric::AllUp),
+            "port-weighted" => Ok(StationMetric::PortWeighted),
+            _ => match name.strip_prefix("at-least-").map(str::parse::<usize>) {
+                Some(Ok(up_chargers)) if up_chargers>0 => Ok(StationMetric::AtLeast(up_chargers)),
+                _ => Err(format!("Unknown station metric '{}'. Expected one of: any-up, all-up, at-least-<K>, port-weighted.", name)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn station_metrics() {
+        // Three chargers, where the third one never reports
+        let chargers = vec![
+            ChargerTimes { power_kw: Some(150.0), times: vec![TimeRange::new(0, 60, true), TimeRange::new(60, 100, false), TimeRange::new(100, 120, true)] },
+            ChargerTimes { power_kw: Some(50.0), times: vec![TimeRange::new(20, 100, true)] },
+            ChargerTimes { power_kw: None, times: Vec::new() },
+        ];
+        let up_times: Vec<u64> = ["any-up", "all-up", "at-least-2", "port-weighted"].iter()
+            .map(|name| name.parse::<StationMetric>().unwrap().up_time(&chargers, 0, 120))
+            .collect();
+        assert_eq!(up_times, vec![120, 40, 40, 80]);
+        assert!("power-weighted".parse::<StationMetric>().is_err());
+        // Only two chargers reported
+        assert_eq!(StationMetric::AtLeast(3).up_time(&chargers, 0, 120), 0);
+        assert_eq!(StationMetric::AnyUp.up_time(&chargers, 50, 110), 60);
+        assert_eq!(StationMetric::AtLeast(3).to_string(), "at-least-3");
+        assert!("at-least-0".parse::<StationMetric>().is_err());
+    }
+
+    #[test]
+    fn power_weighted() {
+        let mut chargers = vec![
+            ChargerTimes { power_kw: Some(150.0), times: vec![TimeRange::new(0, 60, true), TimeRange::new(60, 100, false), TimeRange::new(100, 120, true)] },
+            ChargerTimes { power_kw: Some(50.0), times: vec![TimeRange::new(20, 100, true)] },
+            ChargerTimes { power_kw: None, times: Vec::new() },
+        ];
+        // The unrated charger never reported, so it doesn't count
+        assert_eq!(power_weighted_up_time(&chargers, 0, 120), Some(80));
+        assert_eq!(power_weighted_up_time(&chargers, 0, 60), Some(55));
+        // Once it reports, the station is unrated
+        chargers[2].times = vec![TimeRange::new(0, 120, true)];
+        assert_eq!(power_weighted_up_time(&chargers, 0, 120), None);
+        for charger in &mut chargers {
+            charger.power_kw = None;
+        }
+        assert_eq!(power_weighted_up_time(&chargers, 0, 120), None);
+        assert_eq!(power_weighted_up_time(&[], 0, 120), None);
+    }
+}
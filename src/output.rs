@@ -0,0 +1,140 @@
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::report::UptimeReport;
+
+/// Format of the uptime written by `write_report`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One space separated line per record, without a trailing newline character.
+    #[default]
+    Text,
+    /// A JSON array of records.
+    Json,
+    /// One JSON record per line.
+    Ndjson
+}
+
+impl OutputFormat {
+
+    /// Name of the format, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Text => "text",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+impl fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<OutputFormat, String> {
+        match name {
+            "text" => Ok(OutputFormat::Text),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            _ => Err(format!("Unknown output format '{}'. Expected one of: text, json, ndjson.", name)),
+        }
+    }
+}
+
+/// Kind of records of a report written by `write_report`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RecordKind {
+    /// `StationUptime` records.
+    #[default]
+    Stations,
+    /// `ChargerUptime` records.
+    Chargers,
+    /// `ConnectorUptime` records.
+    Connectors,
+    /// `BucketUptime` records of the uptime series.
+    Buckets,
+    /// `ExcludedTime` records of each station and reason.
+    Exclusions
+}
+
+/// Writes one kind of records of a report in the given format.
+///
+/// ### Input:
+/// - `writer`: Where to write the output
+/// - `report`: The computed uptime
+/// - `format`: Output format
+/// - `records`: Kind of records to write
+///
+/// ### Output:
+/// - `io::Result<()>`: `Error` if writing fails.
+pub fn write_report<W: Write>(writer: &mut W, report: &UptimeReport, format: OutputFormat, records: RecordKind) -> io::Result<()> {
+    match (format, records) {
+        (OutputFormat::Text, RecordKind::Stations) => write!(writer, "{}", report),
+        (OutputFormat::Text, RecordKind::Chargers) => write!(writer, "{}", report.charger_lines()),
+        (OutputFormat::Text, RecordKind::Connectors) => write!(writer, "{}", report.connector_lines()),
+        (OutputFormat::Text, RecordKind::Buckets) => write!(writer, "{}", report.bucket_lines()),
+        (OutputFormat::Text, RecordKind::Exclusions) => write!(writer, "{}", report.excluded_lines()),
+        (_, RecordKind::Stations) => write_records(writer, &report.stations, format),
+        (_, RecordKind::Chargers) => write_records(writer, &report.chargers, format),
+        (_, RecordKind::Connectors) => write_records(writer, &report.connectors, format),
+        (_, RecordKind::Buckets) => write_records(writer, &report.buckets, format),
+        (_, RecordKind::Exclusions) => write_records(writer, &report.excluded, format),
+    }
+}
+
+/// Writes serializable records as a JSON array, or as NDJSON.
+fn write_records<W: Write, T: Serialize>(writer: &mut W, records: &[T], format: OutputFormat) -> io::Result<()> {
+    if format == OutputFormat::Json {
+        serde_json::to_writer_pretty(&mut *writer, records)?;
+        return writeln!(writer);
+    }
+    for record in records {
+        serde_json::to_writer(&mut *writer, record)?;
+        writeln!(writer)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fleet::Fleet;
+
+    fn sample_report() -> UptimeReport {
+        let input = "[Stations]\n0 1001 1002\n\n[Charger Availability Reports]\n1001 0 50 true\n1002 50 100 false\n";
+        Fleet::from_reader(input.as_bytes()).unwrap().compute().unwrap()
+    }
+
+    #[test]
+    fn ndjson_station_records() {
+        let mut output: Vec<u8> = Vec::new();
+        write_report(&mut output, &sample_report(), OutputFormat::Ndjson, RecordKind::Stations).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(),
+            "{\"station_id\":0,\"status\":\"ok\",\"uptime_percent\":50,\"up_nanos\":50,\"reported_nanos\":100,\"first_reported_nanos\":0,\"last_reported_nanos\":100,\"charger_count\":2}\n");
+    }
+
+    #[test]
+    fn json_charger_records() {
+        let mut output: Vec<u8> = Vec::new();
+        write_report(&mut output, &sample_report(), OutputFormat::Json, RecordKind::Chargers).unwrap();
+        let records: serde_json::Value = serde_json::from_slice(&output).unwrap();
+        assert_eq!(records.as_array().unwrap().len(), 2);
+        assert_eq!(records[1]["charger_id"], 1002);
+        assert_eq!(records[1]["uptime_percent"], 0);
+    }
+
+    #[test]
+    fn text_is_unchanged() {
+        let mut output: Vec<u8> = Vec::new();
+        write_report(&mut output, &sample_report(), OutputFormat::Text, RecordKind::Stations).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "0 50");
+    }
+}
@@ -0,0 +1,1072 @@
+use std::fs::File;
+use std::io::{self, Lines, BufReader, BufRead, Error};
+use std::ops::Range;
+use std::sync::LazyLock;
+use regex::Regex;
+
+use crate::attributes::ChargerAttributes;
+use crate::compression::decompress;
+use crate::error::{Location, ParseError, ParseErrorKind, UptimeError};
+use crate::exclusion::{Exclusion, ExclusionTarget};
+use crate::fleet::Fleet;
+use crate::status::ChargerStatus;
+use crate::time_range::TimeRange;
+
+/// The section of input being read.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum InputKind {
+    None,
+    Station,
+    ChargerAvailability,
+    Exclusions,
+    ChargerAttributes
+}
+
+/// What a valid input line held.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum LineContent {
+    /// An empty line, or a section heading.
+    Blank,
+    Station { station_id: u32, chargers: Vec<u32>, connectors: ChargerConnectors },
+    Report { charger_id: u32, time_range: TimeRange },
+    ConnectorReport { charger_id: u32, connector_id: u32, time_range: TimeRange },
+    Exclusion(Exclusion),
+    ChargerAttributes { charger_id: u32, attributes: ChargerAttributes }
+}
+
+/// Connectors listed on a station line, as `(Charger ID, Connector IDs)` for
+/// each charger listed with connectors.
+pub type ChargerConnectors = Vec<(u32, Vec<u32>)>;
+
+/// Takes in a string reference to a file path, and returns an iterator of lines
+/// ### Input:
+/// - `file_path`: A string reference to file path
+///
+/// ### Output:
+/// - `Result<Lines, Error>`: An iterator of lines wrapped in `Ok()` if successful
+///   and `Error` in case of error.
+pub fn read_lines(file_path: &str) -> Result<Lines<BufReader<File>>, Error> {
+
+    let file: File = File::open(file_path)?;
+    Ok(BufReader::new(file).lines())
+}
+
+/// Path that reads input from `stdin` instead of a file.
+pub const STDIN_PATH: &str = "-";
+
+/// Name of `stdin` in error locations.
+pub const STDIN_NAME: &str = "<stdin>";
+
+/// Opens a file for reading, or `stdin` if the path is `-`. Gzip and zstd
+/// compressed input is decompressed while reading.
+/// ### Input:
+/// - `file_path`: A string reference to file path, or `-`
+///
+/// ### Output:
+/// - `Result<Box<dyn BufRead>, Error>`: A buffered reader wrapped in `Ok()` if
+///   successful and `Error` in case of error.
+pub fn open_input(file_path: &str) -> Result<Box<dyn BufRead>, Error> {
+
+    let reader: Box<dyn BufRead> = if file_path == STDIN_PATH {
+        Box::new(io::stdin().lock())
+    } else {
+        let file: File = File::open(file_path)?;
+        Box::new(BufReader::new(file))
+    };
+    decompress(reader, file_path)
+}
+
+/// Name of an input path in error locations. `stdin` is named `<stdin>`.
+pub fn input_name(file_path: &str) -> &str {
+    if file_path == STDIN_PATH { STDIN_NAME } else { file_path }
+}
+
+/// Expands glob patterns in input paths, such as `reports/*.txt`, into the
+/// matching file paths in alphabetical order. Paths without glob characters,
+/// and `-` for `stdin`, are kept as is.
+/// ### Input:
+/// - `patterns`: Input paths and glob patterns, in the order given
+///
+/// ### Output:
+/// - `Result<Vec<String>, UptimeError>`: The file paths wrapped in `Ok()` if
+///   successful. `UptimeError::InvalidArguments` if a pattern is invalid or
+///   matches no files.
+pub fn expand_input_paths(patterns: &[String]) -> Result<Vec<String>, UptimeError> {
+
+    let mut file_paths: Vec<String> = Vec::new();
+    for pattern in patterns {
+        if !pattern.contains(['*', '?', '[']) {
+            file_paths.push(pattern.clone());
+            continue;
+        }
+        let matches = glob::glob(pattern).map_err(|pattern_error| UptimeError::InvalidArguments {
+            message: format!("Invalid file pattern '{}': {}", pattern, pattern_error),
+        })?;
+        let mut matched_paths: Vec<String> = Vec::new();
+        for entry in matches {
+            let path = entry.map_err(|glob_error| UptimeError::Io(glob_error.into()))?;
+            if path.is_file() {
+                matched_paths.push(path.to_string_lossy().into_owned());
+            }
+        }
+        if matched_paths.is_empty() {
+            return Err(UptimeError::InvalidArguments { message: format!("No files match '{}'.", pattern) });
+        }
+        file_paths.extend(matched_paths);
+    }
+    Ok(file_paths)
+}
+
+/// Options for reading input with `construct_maps`.
+#[derive(Clone, Debug, Default)]
+pub struct ReadOptions {
+    /// Keep reading after an invalid line, and return every error found
+    /// as `UptimeError::Multiple` instead of stopping at the first one.
+    pub collect_errors: bool,
+    /// Skip invalid lines instead of failing. Skipped lines are kept in
+    /// `Fleet::rejected_lines`. Takes precedence over `collect_errors`.
+    pub lenient: bool
+}
+
+/// An input line skipped while reading in lenient mode.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RejectedLine {
+    /// Where the line is, including the raw line itself.
+    pub location: Location,
+    /// Short name of the kind of error found in the line.
+    pub category: &'static str,
+    /// The error message for the line.
+    pub message: String
+}
+
+/// Takes in an iterator of input lines, and returns a `Fleet` holding the
+/// station-charger map and the charger-uptime map read from them.
+/// ### Input:
+/// - `lines`: An iterator of lines, such as the one returned by `read_lines`
+///   or `BufRead::lines`
+/// - `options`: Options for handling invalid lines
+///
+/// ### Output:
+/// - `Result<Fleet, UptimeError>`: The `Fleet` wrapped in `Ok()` if successful
+///   and `UptimeError` in case of error. Errors in input lines carry their
+///   line number and column span.
+pub fn construct_maps<I>(lines: I, options: &ReadOptions) -> Result<Fleet, UptimeError>
+    where I: Iterator<Item = Result<String, Error>> {
+
+    let mut fleet = Fleet::new();
+    read_text_into(&mut fleet, lines, options)?;
+    Ok(fleet)
+}
+
+/// Reads several input files one after the other with `read_file`. With
+/// `collect_errors`, keeps reading after a file with invalid lines, and returns
+/// the errors of all files together. Errors reading a file always stop.
+pub(crate) fn read_each_file<F>(file_paths: &[String], options: &ReadOptions, mut read_file: F) -> Result<(), UptimeError>
+    where F: FnMut(&str) -> Result<(), UptimeError> {
+
+    let mut errors: Vec<UptimeError> = Vec::new();
+    for file_path in file_paths {
+        match read_file(file_path) {
+            Ok(()) => {},
+            Err(UptimeError::Multiple(file_errors)) if options.collect_errors => errors.extend(file_errors),
+            Err(UptimeError::Io(io_error)) => return Err(UptimeError::Io(io_error)),
+            Err(file_error) if options.collect_errors => errors.push(file_error),
+            Err(file_error) => return Err(file_error),
+        }
+    }
+    match errors.len() {
+        0 => Ok(()),
+        1 => Err(errors.pop().unwrap()),
+        _ => Err(UptimeError::Multiple(errors)),
+    }
+}
+
+/// Reads input lines in the `[Stations]` / `[Charger Availability Reports]`
+/// format into an existing `Fleet`, with the same validation as `construct_maps`.
+/// ### Input:
+/// - `fleet`: The fleet to add stations and reports to
+/// - `lines`: An iterator of lines
+/// - `options`: Options for handling invalid lines
+///
+/// ### Output:
+/// - `Result<(), UptimeError>`: `UptimeError` in case of error.
+pub fn read_text_into<I>(fleet: &mut Fleet, lines: I, options: &ReadOptions) -> Result<(), UptimeError>
+    where I: Iterator<Item = Result<String, Error>> {
+
+    let mut currently_reading: InputKind = InputKind::None;
+    let mut line_errors = LineErrors::new(options);
+    for (line_index, wrapped_line) in lines.enumerate() {
+        if let Err(line_error) = wrapped_line {
+            return Err(UptimeError::Io(line_error));
+        }
+        let l = wrapped_line.unwrap();
+        let line_result = read_line(fleet, &mut currently_reading, line_index + 1, &l);
+        if let Err(line_error) = line_result {
+            line_errors.handle(fleet, line_error)?;
+        }
+    }
+    line_errors.finish()
+}
+
+/// Handles errors in input lines as set in `ReadOptions`: stops at the
+/// first one, collects all of them, or skips the lines.
+pub(crate) struct LineErrors<'a> {
+    options: &'a ReadOptions,
+    errors: Vec<UptimeError>
+}
+
+impl<'a> LineErrors<'a> {
+
+    pub fn new(options: &'a ReadOptions) -> LineErrors<'a> {
+        LineErrors { options, errors: Vec::new() }
+    }
+
+    /// Handles the error in a line.
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError` if reading should stop here.
+    pub fn handle(&mut self, fleet: &mut Fleet, line_error: UptimeError) -> Result<(), UptimeError> {
+        if self.options.lenient && line_error.location().is_some() {
+            fleet.reject_line(RejectedLine {
+                location: line_error.location().unwrap().clone(),
+                category: line_error.category(),
+                message: line_error.to_string(),
+            });
+        } else if self.options.collect_errors {
+            self.errors.push(line_error);
+        } else {
+            return Err(line_error);
+        }
+        Ok(())
+    }
+
+    /// Returns the collected errors, if any, once all lines are read.
+    pub fn finish(mut self) -> Result<(), UptimeError> {
+        match self.errors.len() {
+            0 => Ok(()),
+            1 => Err(self.errors.pop().unwrap()),
+            _ => Err(UptimeError::Multiple(self.errors)),
+        }
+    }
+}
+
+/// Reads a single input line into the fleet, switching sections on section headings.
+/// ### Input:
+/// - `fleet`: The fleet being read
+/// - `currently_reading`: The section the previous lines were in
+/// - `line_number`: 1-based number of the line, for error locations
+/// - `l`: The raw input line
+///
+/// ### Output:
+/// - `Result<LineContent, UptimeError>`: What the line held wrapped in `Ok()`,
+///   and `UptimeError` in case the line is invalid. The fleet is unchanged in
+///   that case.
+pub(crate) fn read_line(fleet: &mut Fleet, currently_reading: &mut InputKind, line_number: usize, l: &str) -> Result<LineContent, UptimeError> {
+
+    let line_content = parse_line(currently_reading, line_number, l)?;
+    match &line_content {
+        LineContent::Blank => {},
+        LineContent::Station { station_id, chargers, connectors } => {
+            if let Err(mut station_error) = fleet.add_station(*station_id, chargers) {
+                if let UptimeError::ChargerMappedToMultipleStations { location, .. } = &mut station_error {
+                    *location = Some(line_location(line_number, l));
+                }
+                return Err(station_error);
+            }
+            for (charger_id, charger_connectors) in connectors {
+                fleet.add_connectors(*charger_id, charger_connectors);
+            }
+        },
+        LineContent::Report { charger_id, time_range } => fleet.add_report(*charger_id, time_range.clone()),
+        LineContent::ConnectorReport { charger_id, connector_id, time_range } =>
+            fleet.add_connector_report(*charger_id, *connector_id, time_range.clone()),
+        LineContent::Exclusion(exclusion) => fleet.add_exclusion(exclusion.clone()),
+        LineContent::ChargerAttributes { charger_id, attributes } => fleet.add_charger_attributes(*charger_id, attributes.clone()),
+    }
+    Ok(line_content)
+}
+
+/// Parses a single input line, switching sections on section headings.
+/// ### Input:
+/// - `currently_reading`: The section the previous lines were in
+/// - `line_number`: 1-based number of the line, for error locations
+/// - `l`: The raw input line
+///
+/// ### Output:
+/// - `Result<LineContent, UptimeError>`: What the line held wrapped in `Ok()`,
+///   and `UptimeError::Parse` in case the line is invalid.
+pub(crate) fn parse_line(currently_reading: &mut InputKind, line_number: usize, l: &str) -> Result<LineContent, UptimeError> {
+
+    // Spans from the parsers are relative to the trimmed line.
+    let trimmed_offset = l.len() - l.trim_start().len();
+    let locate = |span: Range<usize>| {
+        Box::new(Location::new(line_number, l, span.start + trimmed_offset..span.end + trimmed_offset))
+    };
+    match l.trim() {
+        "" => {},
+        "[Stations]" => *currently_reading = InputKind::Station,
+        "[Charger Availability Reports]" => *currently_reading = InputKind::ChargerAvailability,
+        "[Exclusions]" => *currently_reading = InputKind::Exclusions,
+        "[Charger Attributes]" => *currently_reading = InputKind::ChargerAttributes,
+        trimmed_l => {
+            match currently_reading {
+                InputKind::None => {
+                    return Err(UptimeError::Parse {
+                        location: locate(0..trimmed_l.len()),
+                        kind: ParseErrorKind::MissingSectionHeader,
+                    });
+                },
+                InputKind::Station => {
+                    let (station_id, chargers, connectors) = parse_station_with_connectors(trimmed_l)
+                        .map_err(|station_parse_error| UptimeError::Parse {
+                            location: locate(station_parse_error.span),
+                            kind: station_parse_error.kind,
+                        })?;
+                    return Ok(LineContent::Station { station_id, chargers, connectors });
+                },
+                InputKind::ChargerAvailability if trimmed_l.split(char::is_whitespace).next().is_some_and(|first| first.contains(':')) => {
+                    let (charger_id, connector_id, time_range) = parse_connector_availability(trimmed_l)
+                        .map_err(|connector_parse_error| UptimeError::Parse {
+                            location: locate(connector_parse_error.span),
+                            kind: connector_parse_error.kind,
+                        })?;
+                    return Ok(LineContent::ConnectorReport { charger_id, connector_id, time_range });
+                },
+                InputKind::ChargerAvailability => {
+                    let (charger_id, time_range) = parse_charger_availability(trimmed_l)
+                        .map_err(|charger_parse_error| UptimeError::Parse {
+                            location: locate(charger_parse_error.span),
+                            kind: charger_parse_error.kind,
+                        })?;
+                    return Ok(LineContent::Report { charger_id, time_range });
+                },
+                InputKind::Exclusions => {
+                    let exclusion = parse_exclusion(trimmed_l)
+                        .map_err(|exclusion_parse_error| UptimeError::Parse {
+                            location: locate(exclusion_parse_error.span),
+                            kind: exclusion_parse_error.kind,
+                        })?;
+                    return Ok(LineContent::Exclusion(exclusion));
+                },
+                InputKind::ChargerAttributes => {
+                    let (charger_id, attributes) = parse_charger_attributes(trimmed_l)
+                        .map_err(|attributes_parse_error| UptimeError::Parse {
+                            location: locate(attributes_parse_error.span),
+                            kind: attributes_parse_error.kind,
+                        })?;
+                    return Ok(LineContent::ChargerAttributes { charger_id, attributes });
+                },
+            }
+        }
+    }
+    Ok(LineContent::Blank)
+}
+
+/// Location of a whole input line, without its leading and trailing spaces.
+pub(crate) fn line_location(line_number: usize, l: &str) -> Box<Location> {
+    let trimmed_offset = l.len() - l.trim_start().len();
+    Box::new(Location::new(line_number, l, trimmed_offset..trimmed_offset + l.trim().len()))
+}
+
+/// Parses a line of station info and returns it wrapped in a `Result()`.
+/// ### Input :
+/// - `line`: A string reference containing station id and ids of chargers at a station.
+///   Expected format of `line`:
+///   `<Station ID 1> <Charger ID 1> <Charger ID 2> ... <Charger ID n>`
+///
+/// ### Output:
+/// - `Result<(Station ID, Vec<Charger IDs>), ParseError>`: A tuple of station id and a vector
+///   of charger ids wrapped in `Ok()` if successful and `ParseError` in case of error.
+///   Connectors of chargers are left out, see `parse_station_with_connectors`.
+pub fn parse_station(line: &str) -> Result<(u32, Vec<u32>), ParseError> {
+    parse_station_with_connectors(line).map(|(station_id, chargers, _)| (station_id, chargers))
+}
+
+/// Parses a line of station info, where chargers can list their connectors,
+/// and returns it wrapped in a `Result()`.
+/// ### Input :
+/// - `line`: Like the `line` of `parse_station`, where each charger can be
+///   followed by a colon and a comma separated list of its connector IDs, such
+///   as `0 1001:1,2 1002`
+///
+/// ### Output:
+/// - `Result<(Station ID, Vec<Charger IDs>, ChargerConnectors), ParseError>`: A tuple of
+///   station id, charger ids, and the connector ids of each charger listed with
+///   connectors, wrapped in `Ok()` if successful and `ParseError` in case of error.
+pub fn parse_station_with_connectors(line: &str) -> Result<(u32, Vec<u32>, ChargerConnectors), ParseError> {
+
+    // Splitting on whitespace, keeping the span of each token.
+    // Always yields at least one, possibly empty, token.
+    let mut splits = whitespace_tokens(line);
+    let station_id_span = splits.next().unwrap();
+    let station_id_str = &line[station_id_span.clone()];
+    let station_id_wrapped = station_id_str.parse::<u32>();
+    if station_id_wrapped.is_err() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidStationId { station_id: station_id_str.to_string() },
+            station_id_span));
+    }
+    let station_id = station_id_wrapped.unwrap();
+    let mut chargers: Vec<u32> = Vec::new();
+    let mut connectors: ChargerConnectors = Vec::new();
+
+    for charger_span in splits {
+        let (charger_id_span, connectors_start) = match line[charger_span.clone()].find(':') {
+            Some(colon) => (charger_span.start..charger_span.start + colon, Some(charger_span.start + colon + 1)),
+            None => (charger_span.clone(), None),
+        };
+        let charger_id_str = &line[charger_id_span.clone()];
+        let charger_id_wrapped = charger_id_str.parse::<u32>();
+        if charger_id_wrapped.is_err() {
+            return Err(ParseError::new(
+                ParseErrorKind::InvalidStationChargerId { station_id, charger_id: charger_id_str.to_string() },
+                charger_id_span));
+        }
+        let charger_id = charger_id_wrapped.unwrap();
+        chargers.push(charger_id);
+        if let Some(mut connector_start) = connectors_start {
+            let mut charger_connectors: Vec<u32> = Vec::new();
+            for connector_id_str in line[connector_start..charger_span.end].split(',') {
+                let connector_id_span = connector_start..connector_start + connector_id_str.len();
+                let connector_id = connector_id_str.parse::<u32>().map_err(|_| ParseError::new(
+                    ParseErrorKind::InvalidConnectorId { charger_id, connector_id: connector_id_str.to_string() },
+                    connector_id_span.clone()))?;
+                charger_connectors.push(connector_id);
+                connector_start = connector_id_span.end + 1;
+            }
+            connectors.push((charger_id, charger_connectors));
+        }
+    }
+    Ok((station_id, chargers, connectors))
+
+}
+
+/// Parses a line of charger availability info and returns it wrapped in a `Result()`.
+/// ### Input :
+/// - `line`: A string reference containing charger id, start time, end time,
+///   and up/down status of charger.
+///   Expected format of `line`:
+///   `<Charger ID 1> <start time nanos> <end time nanos> <up (true/false)>`, where
+///   the up status can also be a charger status, such as `faulted`.
+///
+/// ### Output:
+/// - `Result<(Charger ID, TimeRange struct), ParseError>`: A tuple of station id and a struct
+///   `TimeRange` wrapped in `Ok()` if successful and `ParseError` in case of error.
+///   The `TimeRange` struct contains parsed start time, end time, and up/down status of charger.
+pub fn parse_charger_availability(line: &str) -> Result<(u32, TimeRange), ParseError> {
+    let fields = if line.is_ascii() { match_availability_ascii(line) } else { match_availability_regex(line) };
+    if fields.is_none() {
+        return Err(ParseError::new(ParseErrorKind::MalformedAvailabilityEntry, 0..line.len()));
+    }
+    let fields = fields.unwrap();
+    let charger_id_str = &line[fields.charger_id.clone()];
+    let start_time_str = &line[fields.start_time.clone()];
+    let end_time_str = &line[fields.end_time.clone()];
+    let up_status_str = &line[fields.up_status.clone()];
+
+    let charger_id_wrapped = charger_id_str.parse::<u32>();
+    if charger_id_wrapped.is_err() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidAvailabilityChargerId { charger_id: charger_id_str.to_string() },
+            fields.charger_id));
+    }
+    let charger_id = charger_id_wrapped.unwrap();
+    let start_time_wrapped = start_time_str.parse::<u64>();
+    if start_time_wrapped.is_err() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidStartTime { charger_id, start_time: start_time_str.to_string() },
+            fields.start_time));
+    }
+    let end_time_wrapped = end_time_str.parse::<u64>();
+    if end_time_wrapped.is_err() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidEndTime { charger_id, end_time: end_time_str.to_string() },
+            fields.end_time));
+    }
+    let time_range = match report_time_range(start_time_wrapped.unwrap(), end_time_wrapped.unwrap(), up_status_str) {
+        Some(time_range) => time_range,
+        None => return Err(ParseError::new(
+            ParseErrorKind::InvalidUpStatus {
+                charger_id,
+                up_status: up_status_str.to_string(),
+                entry: line.to_string(),
+            },
+            fields.up_status)),
+    };
+    if time_range.from>time_range.to {
+        return Err(ParseError::new(
+            ParseErrorKind::InvertedTimeRange { charger_id, from: time_range.from, to: time_range.to },
+            fields.start_time.start..fields.end_time.end));
+    }
+    Ok((charger_id, time_range))
+}
+
+/// Parses a line of connector availability info and returns it wrapped in a `Result()`.
+/// ### Input :
+/// - `line`: Like the `line` of `parse_charger_availability`, with a charger ID,
+///   a colon and a connector ID in place of the charger ID.
+///   Expected format of `line`:
+///   `<Charger ID 1>:<Connector ID 1> <start time nanos> <end time nanos> <up (true/false)>`
+///
+/// ### Output:
+/// - `Result<(Charger ID, Connector ID, TimeRange struct), ParseError>`: A tuple of charger id,
+///   connector id and a struct `TimeRange` wrapped in `Ok()` if successful and `ParseError`
+///   in case of error.
+pub fn parse_connector_availability(line: &str) -> Result<(u32, u32, TimeRange), ParseError> {
+    let token_end = line.find(char::is_whitespace).unwrap_or(line.len());
+    let Some(colon) = line[..token_end].find(':') else {
+        return Err(ParseError::new(ParseErrorKind::MalformedAvailabilityEntry, 0..line.len()));
+    };
+    let charger_id_str = &line[..colon];
+    let charger_id = charger_id_str.parse::<u32>().map_err(|_| ParseError::new(
+        ParseErrorKind::InvalidAvailabilityChargerId { charger_id: charger_id_str.to_string() },
+        0..colon))?;
+    let connector_id_str = &line[colon + 1..token_end];
+    if connector_id_str.parse::<u32>().is_err() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidConnectorId { charger_id, connector_id: connector_id_str.to_string() },
+            colon + 1..token_end));
+    }
+    // The rest of the line is a charger availability entry of the connector
+    let offset = colon + 1;
+    let (connector_id, time_range) = parse_charger_availability(&line[offset..])
+        .map_err(|mut parse_error| {
+            parse_error.span = parse_error.span.start + offset..parse_error.span.end + offset;
+            match &mut parse_error.kind {
+                ParseErrorKind::MalformedAvailabilityEntry => parse_error.span = 0..line.len(),
+                ParseErrorKind::InvalidStartTime { charger_id: error_charger_id, .. }
+                | ParseErrorKind::InvalidEndTime { charger_id: error_charger_id, .. }
+                | ParseErrorKind::InvalidUpStatus { charger_id: error_charger_id, .. }
+                | ParseErrorKind::InvertedTimeRange { charger_id: error_charger_id, .. } => *error_charger_id = charger_id,
+                _ => {},
+            }
+            parse_error
+        })?;
+    Ok((charger_id, connector_id, time_range))
+}
+
+/// Parses a line of the `[Exclusions]` section and returns it wrapped in a `Result()`.
+/// ### Input :
+/// - `line`: A string reference containing the station or charger, the start
+///   and end time of the excluded window, and a reason code.
+///   Expected format of `line`:
+///   `<station|charger> <ID> <start time nanos> <end time nanos> <reason>`
+///
+/// ### Output:
+/// - `Result<Exclusion, ParseError>`: The exclusion wrapped in `Ok()` if successful
+///   and `ParseError` in case of error.
+pub fn parse_exclusion(line: &str) -> Result<Exclusion, ParseError> {
+
+    let spans: Vec<Range<usize>> = whitespace_tokens(line).collect();
+    if spans.len() != 5 {
+        return Err(ParseError::new(ParseErrorKind::MalformedExclusion, 0..line.len()));
+    }
+    let target_str = &line[spans[0].clone()];
+    let id_str = &line[spans[1].clone()];
+    let id = id_str.parse::<u32>();
+    let target = match (target_str, id) {
+        ("station", Ok(station_id)) => ExclusionTarget::Station(station_id),
+        ("charger", Ok(charger_id)) => ExclusionTarget::Charger(charger_id),
+        ("station" | "charger", Err(_)) => return Err(ParseError::new(
+            ParseErrorKind::InvalidExclusionId { target: target_str.to_string(), id: id_str.to_string() },
+            spans[1].clone())),
+        _ => return Err(ParseError::new(
+            ParseErrorKind::InvalidExclusionTarget { target: target_str.to_string() },
+            spans[0].clone())),
+    };
+    let mut times = [0u64; 2];
+    for (time, span) in times.iter_mut().zip(&spans[2..4]) {
+        let time_str = &line[span.clone()];
+        *time = time_str.parse::<u64>().map_err(|_| ParseError::new(
+            ParseErrorKind::InvalidExclusionTime { time: time_str.to_string() },
+            span.clone()))?;
+    }
+    let [from, to] = times;
+    if from>to {
+        return Err(ParseError::new(ParseErrorKind::InvertedExclusion { from, to }, spans[2].start..spans[3].end));
+    }
+    Ok(Exclusion::new(target, from, to, &line[spans[4].clone()]))
+}
+
+/// Parses a line of the `[Charger Attributes]` section and returns it wrapped in a `Result()`.
+/// ### Input :
+/// - `line`: A string reference containing a charger ID, its power rating in
+///   kW, and optionally its connector type and network. Either can be left
+///   unset with `-`.
+///   Expected format of `line`:
+///   `<Charger ID> <power kW> [<connector type> [<network>]]`
+///
+/// ### Output:
+/// - `Result<(u32, ChargerAttributes), ParseError>`: The charger ID and its
+///   attributes wrapped in `Ok()` if successful and `ParseError` in case of error.
+pub fn parse_charger_attributes(line: &str) -> Result<(u32, ChargerAttributes), ParseError> {
+
+    let spans: Vec<Range<usize>> = whitespace_tokens(line).collect();
+    if !(2..=4).contains(&spans.len()) {
+        return Err(ParseError::new(ParseErrorKind::MalformedChargerAttributes, 0..line.len()));
+    }
+    let charger_id_str = &line[spans[0].clone()];
+    let charger_id = charger_id_str.parse::<u32>().map_err(|_| ParseError::new(
+        ParseErrorKind::InvalidAttributesChargerId { charger_id: charger_id_str.to_string() },
+        spans[0].clone()))?;
+    let power_str = &line[spans[1].clone()];
+    let power_kw = power_str.parse::<f64>().ok()
+        .filter(|power_kw| power_kw.is_finite() && *power_kw > 0.0)
+        .ok_or_else(|| ParseError::new(
+            ParseErrorKind::InvalidPowerRating { charger_id, power: power_str.to_string() },
+            spans[1].clone()))?;
+    let field = |index: usize| spans.get(index)
+        .map(|span| &line[span.clone()])
+        .filter(|value| *value != "-")
+        .map(str::to_string);
+    Ok((charger_id, ChargerAttributes { power_kw, connector_type: field(2), network: field(3) }))
+}
+
+/// Spans of the fields of a charger availability entry, found by
+/// `AVAILABILITY_PATTERN`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct AvailabilityFields {
+    charger_id: Range<usize>,
+    start_time: Range<usize>,
+    end_time: Range<usize>,
+    up_status: Range<usize>
+}
+
+/// Pattern of a charger availability entry. It isn't anchored, so the entry
+/// is the first match anywhere in the line, and text around it is ignored.
+const AVAILABILITY_PATTERN: &str = r"(?<charger_id>\d+)\s+(?<start_time>\d+)\s+(?<end_time>\d+)\s*(?<up_status>\w*)";
+
+/// `AVAILABILITY_PATTERN`, compiled once.
+static AVAILABILITY_REGEX: LazyLock<Regex> = LazyLock::new(|| Regex::new(AVAILABILITY_PATTERN).unwrap());
+
+/// Splits a line on runs of whitespace, like splitting on the regex `\s+`.
+/// Yields the span of each token, including an empty token before leading
+/// and after trailing whitespace, so there is always at least one.
+fn whitespace_tokens(line: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut token_start = Some(0);
+    let mut chars = line.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let start = token_start?;
+        while let Some((index, c)) = chars.next() {
+            if c.is_whitespace() {
+                let mut separator_end = index + c.len_utf8();
+                while let Some((next_index, next_c)) = chars.peek().copied() {
+                    if !next_c.is_whitespace() {
+                        break;
+                    }
+                    separator_end = next_index + next_c.len_utf8();
+                    chars.next();
+                }
+                token_start = Some(separator_end);
+                return Some(start..index);
+            }
+        }
+        token_start = None;
+        Some(start..line.len())
+    })
+}
+
+/// Finds the fields of a charger availability entry in an ASCII line, with the
+/// same result as `AVAILABILITY_PATTERN` without running a regex.
+///
+/// Each run of digits is a candidate start, from left to right. Digit and
+/// whitespace runs are matched greedily, since the pattern can't match a
+/// shorter run where a longer one fails.
+fn match_availability_ascii(line: &str) -> Option<AvailabilityFields> {
+    let bytes = line.as_bytes();
+    // `\s` and `\w` of the regex, restricted to ASCII
+    let is_space = |b: u8| matches!(b, b'\t'..=b'\r' | b' ');
+    let is_word = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let run = |start: usize, matches: &dyn Fn(u8) -> bool| {
+        start + bytes[start..].iter().take_while(|b| matches(**b)).count()
+    };
+    let is_digit = |b: u8| b.is_ascii_digit();
+
+    let mut candidate = 0;
+    while candidate < bytes.len() {
+        if !is_digit(bytes[candidate]) {
+            candidate += 1;
+            continue;
+        }
+        let charger_id_end = run(candidate, &is_digit);
+        let start_time_start = run(charger_id_end, &is_space);
+        let start_time_end = run(start_time_start, &is_digit);
+        let end_time_start = run(start_time_end, &is_space);
+        let end_time_end = run(end_time_start, &is_digit);
+        if start_time_start > charger_id_end && start_time_end > start_time_start
+            && end_time_start > start_time_end && end_time_end > end_time_start {
+            let up_status_start = run(end_time_end, &is_space);
+            let up_status_end = run(up_status_start, &is_word);
+            return Some(AvailabilityFields {
+                charger_id: candidate..charger_id_end,
+                start_time: start_time_start..start_time_end,
+                end_time: end_time_start..end_time_end,
+                up_status: up_status_start..up_status_end,
+            });
+        }
+        candidate = charger_id_end;
+    }
+    None
+}
+
+/// Finds the fields of a charger availability entry with `AVAILABILITY_REGEX`.
+/// Used for lines that are not ASCII, where `\d`, `\s` and `\w` match Unicode.
+fn match_availability_regex(line: &str) -> Option<AvailabilityFields> {
+    let captures = AVAILABILITY_REGEX.captures(line)?;
+    Some(AvailabilityFields {
+        charger_id: captures.name("charger_id").unwrap().range(),
+        start_time: captures.name("start_time").unwrap().range(),
+        end_time: captures.name("end_time").unwrap().range(),
+        up_status: captures.name("up_status").unwrap().range(),
+    })
+}
+
+/// Creates the `TimeRange` of a charger availability entry from its status
+/// field, which is either an up status or a `ChargerStatus` name.
+///
+/// ### Output:
+/// - `Option<TimeRange>`: The `TimeRange`, or `None` if the status field is
+///   neither.
+pub(crate) fn report_time_range(from: u64, to: u64, up_status: &str) -> Option<TimeRange> {
+    if let Some(up) = parse_up_status(up_status) {
+        return Some(TimeRange::new(from, to, up));
+    }
+    let status = up_status.parse::<ChargerStatus>().ok()?;
+    Some(TimeRange::with_status(from, to, status))
+}
+
+/// Parses the up status of a charger availability entry.
+/// `true` and `True` are up, `false` and `False` are down.
+/// Any other value, including a missing up status, is an error.
+///
+/// ### Output:
+/// - `Option<bool>`: The up status, or `None` if it's not a valid value.
+pub fn parse_up_status(up_status: &str) -> Option<bool> {
+    match up_status {
+        "true" | "True" => Some(true),
+        "false" | "False" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tokenizer_matches_regex() {
+        let lines = [
+            "1001 0 50000 true", "1001\t0  50000", "1001 0 50000true", "1001 0 50000 true extra",
+            "x1001 0 50000 false", "1001 0 x 5 6 7 up_status", "12 3", "1001 0 50000 \x0btrue", "",
+            "99999999999 0 5 True", "1 2 3 _", "1 2 3 tr-ue", "a b c", "1 2 34 5 6 7",
+        ];
+        for line in lines {
+            assert_eq!(match_availability_ascii(line), match_availability_regex(line), "line {:?}", line);
+        }
+        assert_eq!(whitespace_tokens(" 1  2 ").collect::<Vec<_>>(), vec![0..0, 1..2, 4..5, 6..6]);
+        assert_eq!(whitespace_tokens("").collect::<Vec<_>>(), vec![0..0]);
+        assert_eq!(whitespace_tokens("0\u{3000}1001").collect::<Vec<_>>(), vec![0..1, 4..8]);
+    }
+
+    #[test]
+    fn parse_station_test_valid() {
+        let station_string = "1 1001 1002";
+        let chargers_vec: Vec<u32> = vec![1001, 1002];
+        let station_id: u32 = 1;
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_ok());
+        let (station_id_parsed, chargers_parsed) = parse_output.unwrap();
+        assert_eq!(station_id, station_id_parsed);
+        assert_eq!(chargers_vec, chargers_parsed);
+    }
+
+    #[test]
+    fn parse_station_wrong_id() {
+        let station_string = "A 1001 1002";
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_err());
+        if let Err(parse_error) = parse_output {
+            assert_eq!(parse_error.to_string(), "Invalid station ID: 'A'");
+        } else {
+            panic!("Parsing was successful!");
+        }
+    }
+
+    #[test]
+    fn parse_station_missing_chargers() {
+        let station_string = "1";
+        let chargers_vec: Vec<u32> = Vec::new();
+        let station_id: u32 = 1;
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_ok());
+        let (station_id_parsed, chargers_parsed) = parse_output.unwrap();
+        assert_eq!(station_id, station_id_parsed);
+        assert_eq!(chargers_vec, chargers_parsed);
+    }
+
+    #[test]
+    fn parse_station_empty() {
+        let station_string = "";
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_err());
+        if let Err(parse_error) = parse_output {
+            assert_eq!(parse_error.to_string(), "Invalid station ID: ''");
+        } else {
+            panic!("Parsing was successful!");
+        }
+    }
+
+    #[test]
+    fn parse_station_invalid_charger() {
+        let station_string = "1 4294967296";
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_err());
+        if let Err(parse_error) = parse_output {
+            assert_eq!(parse_error.to_string(), "Invalid station entry for Station ID: 1.\nCould not parse charger ID.");
+        } else {
+            panic!("Parsing was successful!");
+        }
+    }
+
+    #[test]
+    fn parse_station_max_charger_id() {
+        let station_string = "1 4294967295";
+        let chargers_vec: Vec<u32> = vec![4294967295];
+        let station_id: u32 = 1;
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_ok());
+        let (station_id_parsed, chargers_parsed) = parse_output.unwrap();
+        assert_eq!(station_id, station_id_parsed);
+        assert_eq!(chargers_vec, chargers_parsed);
+    }
+
+    #[test]
+    fn parse_station_neg_charger_id() {
+        let station_string = "1 -1";
+        let parse_output = parse_station(station_string);
+        assert!(parse_output.is_err());
+        if let Err(parse_error) = parse_output {
+            assert_eq!(parse_error.to_string(), "Invalid station entry for Station ID: 1.\nCould not parse charger ID.");
+        } else {
+            panic!("Parsing was successful!");
+        }
+    }
+
+    #[test]
+    fn parse_charger_valid() {
+        let charger_string = "1 1000 10000 true";
+        let charger_id: u32 = 1;
+        let time_range = TimeRange {
+            from: 1000,
+            to: 10000,
+            up: true,
+            status: None,
+        };
+        let parse_output = parse_charger_availability(charger_string);
+        assert!(parse_output.is_ok());
+        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
+        assert_eq!(charger_id, charger_id_parsed);
+        assert_eq!(time_range, time_range_parsed);
+    }
+
+
+    #[test]
+    fn parse_charger_invalid_id() {
+        let charger_string = "A 1000 10000 true";
+        let parse_output = parse_charger_availability(charger_string);
+        assert!(parse_output.is_err());
+        if let Err(parse_error) = parse_output {
+            assert_eq!(parse_error.to_string(), "Could not parse charger availability entry. Please check the input file.");
+        } else {
+            panic!("Parsing was successful!");
+        }
+    }
+
+    #[test]
+    fn parse_charger_up_false() {
+        let charger_string = "1 1000 10000 false";
+        let charger_id: u32 = 1;
+        let time_range = TimeRange {
+            from: 1000,
+            to: 10000,
+            up: false,
+            status: None,
+        };
+        let parse_output = parse_charger_availability(charger_string);
+        assert!(parse_output.is_ok());
+        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
+        assert_eq!(charger_id, charger_id_parsed);
+        assert_eq!(time_range, time_range_parsed);
+    }
+
+    #[test]
+    fn parse_charger_up_missing_rejected() {
+        let charger_string = "1 1000 10000";
+        let parse_output = parse_charger_availability(charger_string);
+        assert!(matches!(parse_output.unwrap_err().kind, ParseErrorKind::InvalidUpStatus { charger_id: 1, .. }));
+    }
+
+    #[test]
+    fn parse_charger_up_true_pascal() {
+        let charger_string = "1 1000 10000 True";
+        let charger_id: u32 = 1;
+        let time_range = TimeRange {
+            from: 1000,
+            to: 10000,
+            up: true,
+            status: None,
+        };
+        let parse_output = parse_charger_availability(charger_string);
+        assert!(parse_output.is_ok());
+        let (charger_id_parsed, time_range_parsed) = parse_output.unwrap();
+        assert_eq!(charger_id, charger_id_parsed);
+        assert_eq!(time_range, time_range_parsed);
+    }
+
+    #[test]
+    fn parse_exclusion_entries() {
+        assert_eq!(parse_exclusion("station 0 1000 2000 utility_outage"),
+            Ok(Exclusion::new(ExclusionTarget::Station(0), 1000, 2000, "utility_outage")));
+        assert_eq!(parse_exclusion("charger  1001\t0 50 maintenance"),
+            Ok(Exclusion::new(ExclusionTarget::Charger(1001), 0, 50, "maintenance")));
+        let errors: Vec<(ParseErrorKind, Range<usize>)> = ["station 0 1000 2000", "site 0 1 2 x", "charger x 1 2 x", "station 0 1 -2 x", "station 0 9 2 x"]
+            .iter()
+            .map(|line| parse_exclusion(line).unwrap_err())
+            .map(|parse_error| (parse_error.kind, parse_error.span))
+            .collect();
+        assert_eq!(errors, vec![
+            (ParseErrorKind::MalformedExclusion, 0..19),
+            (ParseErrorKind::InvalidExclusionTarget { target: "site".to_string() }, 0..4),
+            (ParseErrorKind::InvalidExclusionId { target: "charger".to_string(), id: "x".to_string() }, 8..9),
+            (ParseErrorKind::InvalidExclusionTime { time: "-2".to_string() }, 12..14),
+            (ParseErrorKind::InvertedExclusion { from: 9, to: 2 }, 10..13),
+        ]);
+    }
+
+    #[test]
+    fn parse_charger_attribute_entries() {
+        assert_eq!(parse_charger_attributes("1001 350"), Ok((1001, ChargerAttributes::new(350.0))));
+        assert_eq!(parse_charger_attributes("1002 7.4 - volta"), Ok((1002, ChargerAttributes {
+            power_kw: 7.4,
+            connector_type: None,
+            network: Some("volta".to_string()),
+        })));
+        let errors: Vec<(ParseErrorKind, Range<usize>)> = ["1001", "x 50", "1001 0 CCS1", "1001 inf", "1001 50 CCS1 a b"]
+            .iter()
+            .map(|line| parse_charger_attributes(line).unwrap_err())
+            .map(|parse_error| (parse_error.kind, parse_error.span))
+            .collect();
+        assert_eq!(errors, vec![
+            (ParseErrorKind::MalformedChargerAttributes, 0..4),
+            (ParseErrorKind::InvalidAttributesChargerId { charger_id: "x".to_string() }, 0..1),
+            (ParseErrorKind::InvalidPowerRating { charger_id: 1001, power: "0".to_string() }, 5..6),
+            (ParseErrorKind::InvalidPowerRating { charger_id: 1001, power: "inf".to_string() }, 5..8),
+            (ParseErrorKind::MalformedChargerAttributes, 0..16),
+        ]);
+    }
+
+    #[test]
+    fn parse_connectors() {
+        assert_eq!(parse_station_with_connectors("0 1001:1,2 1002"), Ok((0, vec![1001, 1002], vec![(1001, vec![1, 2])])));
+        assert_eq!(parse_station("0 1001:1,2 1002"), Ok((0, vec![1001, 1002])));
+        let station_error = parse_station_with_connectors("0 1001:1,x").unwrap_err();
+        assert_eq!((station_error.kind, station_error.span),
+            (ParseErrorKind::InvalidConnectorId { charger_id: 1001, connector_id: "x".to_string() }, 9..10));
+
+        assert_eq!(parse_connector_availability("1001:2 0 50 true"), Ok((1001, 2, TimeRange::new(0, 50, true))));
+        let errors: Vec<(ParseErrorKind, Range<usize>)> = ["1001: 0 50 true", "x:2 0 50 true", "1001:2 0 x true"]
+            .iter()
+            .map(|line| parse_connector_availability(line).unwrap_err())
+            .map(|parse_error| (parse_error.kind, parse_error.span))
+            .collect();
+        assert_eq!(errors, vec![
+            (ParseErrorKind::InvalidConnectorId { charger_id: 1001, connector_id: String::new() }, 5..5),
+            (ParseErrorKind::InvalidAvailabilityChargerId { charger_id: "x".to_string() }, 0..1),
+            (ParseErrorKind::MalformedAvailabilityEntry, 0..15),
+        ]);
+    }
+
+    #[test]
+    fn parse_charger_status() {
+        let (_, time_range) = parse_charger_availability("1 1000 10000 Faulted").unwrap();
+        assert_eq!(time_range, TimeRange::with_status(1000, 10000, ChargerStatus::Faulted));
+        assert!(!time_range.up);
+        let (_, time_range) = parse_charger_availability("1 1000 10000 charging").unwrap();
+        assert!(time_range.up);
+        let parse_error = parse_charger_availability("1 1000 10000 broken").unwrap_err();
+        assert_eq!(parse_error.span, 13..19);
+    }
+
+    #[test]
+    fn parse_station_error_span() {
+        let parse_output = parse_station("1 1001  x1002");
+        assert_eq!(parse_output.unwrap_err().span, 8..13);
+    }
+
+    #[test]
+    fn parse_charger_error_span() {
+        let parse_output = parse_charger_availability("1001 0 50000 maybe");
+        assert_eq!(parse_output.unwrap_err().span, 13..18);
+    }
+
+    #[test]
+    fn construct_maps_locates_errors() {
+        let input = "[Stations]\n  0 1001 x\n";
+        let construct_output = construct_maps(input.as_bytes().lines(), &ReadOptions::default());
+        let location = construct_output.unwrap_err().location().cloned().unwrap();
+        assert_eq!(location.line, 2);
+        assert_eq!(location.columns, 10..11);
+        assert_eq!(location.source_line, "  0 1001 x");
+    }
+
+    #[test]
+    fn construct_maps_collects_errors() {
+        let input = "[Stations]\nA 1001\n1 1002\n[Charger Availability Reports]\n1002 10 0 true\n1002 0 10 true\n";
+        let options = ReadOptions { collect_errors: true, ..ReadOptions::default() };
+        match construct_maps(input.as_bytes().lines(), &options) {
+            Err(UptimeError::Multiple(errors)) => {
+                let lines: Vec<usize> = errors.iter().map(|error| error.location().unwrap().line).collect();
+                assert_eq!(lines, vec![2, 5]);
+            },
+            other => panic!("Expected multiple errors, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn construct_maps_lenient_skips_lines() {
+        let input = "[Stations]\n0 1001\n1 1001\n[Charger Availability Reports]\n1001 0 10 true\n1001 20 10 true\n1001 x\n1001 10 20 false\n";
+        let options = ReadOptions { lenient: true, ..ReadOptions::default() };
+        let fleet = construct_maps(input.as_bytes().lines(), &options).unwrap();
+        assert_eq!(fleet.charger_uptime_map()[&1001].len(), 2);
+        assert_eq!(fleet.station_of(1001), Some(0));
+        let skipped_lines: Vec<usize> = fleet.rejected_lines().iter()
+            .map(|rejected| rejected.location.line)
+            .collect();
+        assert_eq!(skipped_lines, vec![3, 6, 7]);
+        let counts = fleet.rejected_line_counts();
+        assert_eq!(counts["charger mapped to multiple stations"], 1);
+        assert_eq!(counts["start time after end time"], 1);
+        assert_eq!(counts["malformed availability entry"], 1);
+    }
+
+    #[test]
+    fn parse_charger_before_gt_after() {
+        let charger_string = "1 10000 1000 true";
+        let parse_output = parse_charger_availability(charger_string);
+        assert!(parse_output.is_err());
+        if let Err(parse_error) = parse_output {
+            assert_eq!(parse_error.to_string(), "Invalid charger availability entry for charger ID 1!\nAvailability from is after availability to.");
+        } else {
+            panic!("Parsing was successful!");
+        }
+    }
+
+    #[test]
+    fn expand_input_paths_globs() {
+        let patterns = vec!["-".to_string(), "src/*.rs".to_string(), "input.txt".to_string()];
+        let file_paths = expand_input_paths(&patterns).unwrap();
+        assert_eq!(file_paths.first().map(String::as_str), Some("-"));
+        assert_eq!(file_paths.last().map(String::as_str), Some("input.txt"));
+        assert!(file_paths.iter().any(|file_path| file_path.ends_with("parser.rs")));
+        assert!(expand_input_paths(&["src/*.missing".to_string()]).is_err());
+    }
+}
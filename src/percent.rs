@@ -0,0 +1,193 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+/// Largest number of decimal places of an `UptimePercent`.
+pub const MAX_PRECISION: u8 = 9;
+
+/// How an uptime percentage is rounded to its precision.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Rounding {
+    /// Round down, so an uptime is never reported higher than it was.
+    #[default]
+    Floor,
+    /// Round to the nearest value, with halves rounded up.
+    Nearest,
+    /// Round up.
+    Ceil
+}
+
+impl Rounding {
+
+    /// Name of the rounding mode, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Rounding::Floor => "floor",
+            Rounding::Nearest => "nearest",
+            Rounding::Ceil => "ceil",
+        }
+    }
+}
+
+impl fmt::Display for Rounding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Rounding {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Rounding, String> {
+        match name {
+            "floor" => Ok(Rounding::Floor),
+            "nearest" => Ok(Rounding::Nearest),
+            "ceil" => Ok(Rounding::Ceil),
+            _ => Err(format!("Unknown rounding mode '{}'. Expected one of: floor, nearest, ceil.", name)),
+        }
+    }
+}
+
+/// Precision and rounding of uptime percentages. The default is a whole
+/// percent, rounded down.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PercentFormat {
+    /// Number of decimal places, up to `MAX_PRECISION`.
+    pub precision: u8,
+    pub rounding: Rounding
+}
+
+/// An uptime percentage in the range [0-100], with a fixed number of decimal
+/// places.
+///
+/// It is computed exactly from the up and reported time, and displays with
+/// all of its decimal places, such as `96.99` or `97.00`. In JSON, it is a
+/// number.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UptimePercent {
+    /// The percentage multiplied by `10^precision`.
+    scaled: u64,
+    precision: u8
+}
+
+impl UptimePercent {
+
+    /// Computes `up_time / total_time` as a percentage.
+    /// ### Input:
+    /// - `up_time`: Time up, at most `total_time`
+    /// - `total_time`: Time the percentage is of
+    /// - `format`: Precision and rounding of the percentage
+    ///
+    /// ### Output:
+    /// - `UptimePercent`: The percentage. 0 if `total_time` is 0.
+    pub fn new(up_time: u64, total_time: u64, format: PercentFormat) -> UptimePercent {
+        let precision = format.precision.min(MAX_PRECISION);
+        if total_time==0 {
+            // Only zero length windows were reported
+            return UptimePercent { scaled: 0, precision };
+        }
+
+        // At most 2^64 * 10^11, so it can't overflow a u128
+        let numerator = up_time as u128 * 100 * 10u128.pow(precision as u32);
+        let total_time = total_time as u128;
+        let quotient = numerator / total_time;
+        let remainder = numerator % total_time;
+        let rounded_up = match format.rounding {
+            Rounding::Floor => false,
+            Rounding::Nearest => remainder * 2 >= total_time,
+            Rounding::Ceil => remainder > 0,
+        };
+        let scaled = quotient + rounded_up as u128;
+        UptimePercent { scaled: scaled as u64, precision }
+    }
+
+    /// The percentage multiplied by `10^precision`.
+    pub fn scaled(&self) -> u64 {
+        self.scaled
+    }
+
+    /// Number of decimal places.
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    /// The percentage as a floating point number.
+    pub fn as_f64(&self) -> f64 {
+        self.scaled as f64 / 10u64.pow(self.precision as u32) as f64
+    }
+}
+
+impl From<u8> for UptimePercent {
+    /// A whole percentage.
+    fn from(percent: u8) -> UptimePercent {
+        UptimePercent { scaled: percent as u64, precision: 0 }
+    }
+}
+
+impl PartialEq<u8> for UptimePercent {
+    /// Whether the percentage is exactly the whole percentage `other`.
+    fn eq(&self, other: &u8) -> bool {
+        self.scaled == *other as u64 * 10u64.pow(self.precision as u32)
+    }
+}
+
+impl fmt::Display for UptimePercent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.precision==0 {
+            return write!(f, "{}", self.scaled);
+        }
+        let divisor = 10u64.pow(self.precision as u32);
+        write!(f, "{}.{:0width$}", self.scaled / divisor, self.scaled % divisor, width = self.precision as usize)
+    }
+}
+
+impl Serialize for UptimePercent {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.precision==0 {
+            serializer.serialize_u64(self.scaled)
+        } else {
+            serializer.serialize_f64(self.as_f64())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn percent(up_time: u64, total_time: u64, precision: u8, rounding: Rounding) -> String {
+        UptimePercent::new(up_time, total_time, PercentFormat { precision, rounding }).to_string()
+    }
+
+    #[test]
+    fn rounding_modes() {
+        assert_eq!(percent(9699, 10000, 0, Rounding::Floor), "96");
+        assert_eq!(percent(9699, 10000, 0, Rounding::Nearest), "97");
+        assert_eq!(percent(9699, 10000, 2, Rounding::Floor), "96.99");
+        assert_eq!(percent(97, 100, 2, Rounding::Ceil), "97.00");
+        assert_eq!(percent(2, 3, 3, Rounding::Nearest), "66.667");
+        assert_eq!(percent(2, 3, 3, Rounding::Ceil), "66.667");
+        assert_eq!(percent(1, 8, 1, Rounding::Nearest), "12.5");
+        assert_eq!(percent(1, 16, 1, Rounding::Nearest), "6.3");
+        assert_eq!(percent(0, 0, 2, Rounding::Ceil), "0.00");
+    }
+
+    #[test]
+    fn exact_on_large_spans() {
+        // Dividing the total by 100 first would give 98 here
+        assert_eq!(percent(9900, 10199, 0, Rounding::Floor), "97");
+        let total_time = u64::MAX / 3;
+        assert_eq!(percent(total_time / 100 * 97 - 1, total_time, 0, Rounding::Floor), "96");
+        assert_eq!(percent(u64::MAX, u64::MAX, MAX_PRECISION, Rounding::Floor), "100.000000000");
+    }
+
+    #[test]
+    fn serializes_as_number() {
+        let whole = UptimePercent::new(1, 2, PercentFormat::default());
+        assert_eq!(serde_json::to_string(&whole).unwrap(), "50");
+        assert_eq!(whole, 50);
+        let fractional = UptimePercent::new(9699, 10000, PercentFormat { precision: 2, rounding: Rounding::Floor });
+        assert_eq!(serde_json::to_string(&fractional).unwrap(), "96.99");
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::percent::UptimePercent;
+use crate::status::ChargerStatus;
+
+/// Whether a station had availability data to compute uptime from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StationStatus {
+    /// Uptime was computed from the station's reports.
+    Ok,
+    /// No charger at the station reported, no report was inside a reporting
+    /// window with `UnreportedTime::Unknown`, or all of the reported time was
+    /// excluded. Uptime is set by the `NoDataPolicy`.
+    NoData
+}
+
+/// Uptime computed for a single station.
+///
+/// This is also the schema of each station record in JSON and NDJSON output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StationUptime {
+    pub station_id: u32,
+    pub status: StationStatus,
+    /// Uptime percentage in the range [0-100]. Rounded down to the nearest
+    /// percent, unless set otherwise in `ComputeOptions::percent_format`.
+    /// `None` for a station without data with `NoDataPolicy::Unknown`, which
+    /// is `null` in JSON output.
+    pub uptime_percent: Option<UptimePercent>,
+    /// Time any charger at the station was reported up, in nanoseconds.
+    pub up_nanos: u64,
+    /// Time from `first_reported_nanos` to `last_reported_nanos`. Gaps in
+    /// between count as down time. This and the times above are 0 for a
+    /// station without data.
+    pub reported_nanos: u64,
+    /// Start of the earliest report of any charger at the station.
+    pub first_reported_nanos: u64,
+    /// End of the latest report of any charger at the station.
+    pub last_reported_nanos: u64,
+    /// Number of chargers at the station, including ones without reports.
+    pub charger_count: usize,
+    /// Excluded time between `first_reported_nanos` and `last_reported_nanos`,
+    /// which is left out of `up_nanos` and `reported_nanos`. Left out of JSON
+    /// output if there is none.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub excluded_nanos: u64,
+    /// Uptime percentage with each charger weighted by its power rating, over
+    /// the same reported time as `uptime_percent`. Computed alongside the
+    /// station metric from the `[Charger Attributes]` section. `None` if the
+    /// station is unrated: any of its chargers that reported has no power
+    /// rating, or it has no data. Left out of JSON output if `None`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_weighted_uptime_percent: Option<UptimePercent>,
+    /// Mean up time of the chargers weighted by their power ratings, rounded
+    /// down. `None` like `power_weighted_uptime_percent`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power_weighted_up_nanos: Option<u64>
+}
+
+/// Uptime computed for a single charger, over the span of its own reports.
+///
+/// This is also the schema of each charger record in JSON and NDJSON output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ChargerUptime {
+    pub charger_id: u32,
+    /// ID of the station the charger belongs to.
+    pub station_id: u32,
+    /// Uptime percentage in the range [0-100]. Rounded down to the nearest
+    /// percent, unless set otherwise in `ComputeOptions::percent_format`.
+    /// `None` if all of the charger's reported time was excluded, which is
+    /// `unknown` in text output and `null` in JSON output.
+    pub uptime_percent: Option<UptimePercent>,
+    /// Time from the start of the charger's earliest report to the end of its
+    /// latest report, in nanoseconds. Gaps in between count as down time.
+    pub reported_nanos: u64,
+    /// Time the charger was reported up, in nanoseconds.
+    pub up_nanos: u64,
+    /// Time the charger spent in each charger status, in nanoseconds, over the
+    /// same resolved reports as `up_nanos`, without excluded time. Empty if
+    /// the charger only reported `true`/`false`, and left out of JSON output then.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub status_nanos: BTreeMap<ChargerStatus, u64>,
+    /// Excluded time of the charger or its station within the charger's
+    /// reported span, which is left out of `up_nanos` and `reported_nanos`.
+    /// Left out of JSON output if there is none.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub excluded_nanos: u64
+}
+
+/// Uptime computed for a single connector of a charger, over the span of its
+/// own reports.
+///
+/// This is also the schema of each connector record in JSON and NDJSON output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ConnectorUptime {
+    /// ID of the charger the connector belongs to.
+    pub charger_id: u32,
+    /// ID of the connector, unique within its charger.
+    pub connector_id: u32,
+    /// ID of the station the charger belongs to.
+    pub station_id: u32,
+    /// Uptime percentage in the range [0-100], like `ChargerUptime::uptime_percent`.
+    pub uptime_percent: UptimePercent,
+    /// Time from the start of the connector's earliest report to the end of
+    /// its latest report, in nanoseconds. Gaps in between count as down time.
+    pub reported_nanos: u64,
+    /// Time the connector was reported up, in nanoseconds.
+    pub up_nanos: u64,
+    /// Excluded time of the charger or its station within the connector's
+    /// reported span. Left out of JSON output if there is none.
+    #[serde(skip_serializing_if = "is_zero")]
+    pub excluded_nanos: u64
+}
+
+/// Uptime of a station over a single bucket of an uptime series.
+///
+/// This is also the schema of each bucket record in JSON and NDJSON output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct BucketUptime {
+    pub station_id: u32,
+    /// Start of the bucket (inclusive).
+    pub bucket_start_nanos: u64,
+    /// End of the bucket (exclusive).
+    pub bucket_end_nanos: u64,
+    /// Uptime percentage in the range [0-100]. Rounded down to the nearest
+    /// percent, unless set otherwise in `ComputeOptions::percent_format`.
+    pub uptime_percent: UptimePercent,
+    /// Time any charger at the station was reported up within the bucket.
+    pub up_nanos: u64,
+    /// Part of the station's reported time within the bucket.
+    pub reported_nanos: u64
+}
+
+/// Time excluded from a station's uptime for a single reason, within the
+/// station's reported span. Time excluded for several reasons at once counts
+/// towards each of them.
+///
+/// This is also the schema of each exclusion record in JSON and NDJSON output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ExcludedTime {
+    pub station_id: u32,
+    pub reason: String,
+    pub excluded_nanos: u64
+}
+
+/// A charger that reported availability but is not listed under any station.
+/// Its reports don't count towards any station's uptime.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct OrphanedCharger {
+    pub charger_id: u32,
+    /// Number of availability reports of the charger.
+    pub report_count: usize,
+    /// Time covered by the charger's reports, up or down, in nanoseconds.
+    /// Overlapping reports are counted once.
+    pub reported_nanos: u64
+}
+
+/// Result of an uptime computation over a `Fleet`.
+///
+/// Stations are held in ascending order of Station ID. The `Display`
+/// implementation writes one `<Station ID> <uptime>` line per station,
+/// without a trailing newline character. The uptime of a station without
+/// data reported as unknown is written as `unknown`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UptimeReport {
+    pub stations: Vec<StationUptime>,
+    /// Uptime of each charger with at least one report that belongs to a
+    /// station, in ascending order of Station ID, then Charger ID.
+    pub chargers: Vec<ChargerUptime>,
+    /// Uptime of each connector, for chargers with connector reports, in
+    /// ascending order of Station ID, then Charger ID, then Connector ID.
+    pub connectors: Vec<ConnectorUptime>,
+    /// Uptime series of each station, if computed with a bucket width, in
+    /// ascending order of Station ID, then bucket start.
+    pub buckets: Vec<BucketUptime>,
+    /// Excluded time of each station by reason, in ascending order of
+    /// Station ID, then reason.
+    pub excluded: Vec<ExcludedTime>,
+    /// Number of conflicting overlaps resolved by the conflict policy, for
+    /// each Charger ID that had any.
+    pub conflicts_resolved: BTreeMap<u32, usize>
+}
+
+impl UptimeReport {
+
+    /// Creates a report from station uptimes in any order.
+    /// ### Input:
+    /// - `stations`: Uptime of each station
+    ///
+    /// ### Output:
+    /// - `UptimeReport`: The report, with stations sorted by Station ID.
+    pub fn new(mut stations: Vec<StationUptime>) -> UptimeReport {
+        stations.sort_by_key(|station| station.station_id);
+        UptimeReport { stations, chargers: Vec::new(), connectors: Vec::new(), buckets: Vec::new(), excluded: Vec::new(), conflicts_resolved: BTreeMap::new() }
+    }
+
+    /// Sets the charger uptimes of the report, from charger uptimes in any order.
+    pub fn set_chargers(&mut self, mut chargers: Vec<ChargerUptime>) {
+        chargers.sort_by_key(|charger| (charger.station_id, charger.charger_id));
+        self.chargers = chargers;
+    }
+
+    /// Sets the connector uptimes of the report, from connector uptimes in any order.
+    pub fn set_connectors(&mut self, mut connectors: Vec<ConnectorUptime>) {
+        connectors.sort_by_key(|connector| (connector.station_id, connector.charger_id, connector.connector_id));
+        self.connectors = connectors;
+    }
+
+    /// Sets the uptime series of the report, from bucket uptimes in any order.
+    pub fn set_buckets(&mut self, mut buckets: Vec<BucketUptime>) {
+        buckets.sort_by_key(|bucket| (bucket.station_id, bucket.bucket_start_nanos));
+        self.buckets = buckets;
+    }
+
+    /// Sets the excluded time of the report, from excluded time in any order.
+    pub fn set_excluded(&mut self, mut excluded: Vec<ExcludedTime>) {
+        excluded.sort_by(|first, second| (first.station_id, &first.reason).cmp(&(second.station_id, &second.reason)));
+        self.excluded = excluded;
+    }
+
+    /// Writes one `<Station ID> <bucket start nanos> <uptime> <reported nanos> <up nanos>`
+    /// line per bucket, without a trailing newline character.
+    pub fn bucket_lines(&self) -> String {
+        let lines: Vec<String> = self.buckets.iter()
+            .map(|bucket| format!("{} {} {} {} {}",
+                bucket.station_id,
+                bucket.bucket_start_nanos,
+                bucket.uptime_percent,
+                bucket.reported_nanos,
+                bucket.up_nanos))
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Writes one `<Charger ID> <Station ID> <uptime> <reported nanos> <up nanos>`
+    /// line per charger, followed by `<status>=<nanos>` for each charger status
+    /// it reported, without a trailing newline character.
+    pub fn charger_lines(&self) -> String {
+        let lines: Vec<String> = self.chargers.iter()
+            .map(|charger| {
+                let uptime_percent = charger.uptime_percent.map_or("unknown".to_string(), |uptime_percent| uptime_percent.to_string());
+                let mut line = format!("{} {} {} {} {}",
+                    charger.charger_id,
+                    charger.station_id,
+                    uptime_percent,
+                    charger.reported_nanos,
+                    charger.up_nanos);
+                for (status, nanos) in &charger.status_nanos {
+                    line.push_str(&format!(" {}={}", status, nanos));
+                }
+                line
+            })
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Writes one `<Charger ID>:<Connector ID> <Station ID> <uptime> <reported nanos> <up nanos>`
+    /// line per connector, without a trailing newline character.
+    pub fn connector_lines(&self) -> String {
+        let lines: Vec<String> = self.connectors.iter()
+            .map(|connector| format!("{}:{} {} {} {} {}",
+                connector.charger_id,
+                connector.connector_id,
+                connector.station_id,
+                connector.uptime_percent,
+                connector.reported_nanos,
+                connector.up_nanos))
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Writes one `<Station ID> <reason> <excluded nanos>` line per station and
+    /// reason, without a trailing newline character.
+    pub fn excluded_lines(&self) -> String {
+        let lines: Vec<String> = self.excluded.iter()
+            .map(|excluded| format!("{} {} {}", excluded.station_id, excluded.reason, excluded.excluded_nanos))
+            .collect();
+        lines.join("\n")
+    }
+
+    /// Looks up the uptime of a station by its ID.
+    pub fn get(&self, station_id: u32) -> Option<&StationUptime> {
+        self.stations
+            .binary_search_by_key(&station_id, |station| station.station_id)
+            .ok()
+            .map(|index| &self.stations[index])
+    }
+}
+
+impl fmt::Display for UptimeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Printing without trailing newline character
+        for (i, station) in self.stations.iter().enumerate() {
+            if i>0 {
+                writeln!(f)?;
+            }
+            match station.uptime_percent {
+                Some(uptime_percent) => write!(f, "{} {}", station.station_id, uptime_percent)?,
+                None => write!(f, "{} unknown", station.station_id)?,
+            }
+            // Only rated stations have a power-weighted uptime
+            if let Some(power_weighted_uptime_percent) = station.power_weighted_uptime_percent {
+                write!(f, " power_weighted={}", power_weighted_uptime_percent)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn is_zero(nanos: &u64) -> bool {
+    *nanos == 0
+}
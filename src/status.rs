@@ -0,0 +1,261 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::time_range::TimeRange;
+
+/// Status of a charger reported by telemetry, in place of a `true`/`false`
+/// up status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChargerStatus {
+    /// Idle, and ready to charge.
+    Available,
+    /// Charging a vehicle.
+    Charging,
+    /// Out of order because of a fault.
+    Faulted,
+    /// Not connected to the network.
+    Offline,
+    /// Taken out of service for maintenance.
+    Maintenance,
+    /// Held for a driver who booked it.
+    Reserved,
+    /// The charger reported, but its status is unknown.
+    Unknown
+}
+
+impl ChargerStatus {
+
+    /// All statuses, in order.
+    pub const ALL: [ChargerStatus; 7] = [
+        ChargerStatus::Available,
+        ChargerStatus::Charging,
+        ChargerStatus::Faulted,
+        ChargerStatus::Offline,
+        ChargerStatus::Maintenance,
+        ChargerStatus::Reserved,
+        ChargerStatus::Unknown,
+    ];
+
+    /// Name of the status, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChargerStatus::Available => "available",
+            ChargerStatus::Charging => "charging",
+            ChargerStatus::Faulted => "faulted",
+            ChargerStatus::Offline => "offline",
+            ChargerStatus::Maintenance => "maintenance",
+            ChargerStatus::Reserved => "reserved",
+            ChargerStatus::Unknown => "unknown",
+        }
+    }
+
+    /// Bit of the status in `UpStatuses`.
+    fn bit(&self) -> u8 {
+        1 << (*self as u8)
+    }
+}
+
+impl fmt::Display for ChargerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for ChargerStatus {
+    type Err = String;
+
+    /// Parses a status name in any case, such as `available` or `Available`.
+    fn from_str(name: &str) -> Result<ChargerStatus, String> {
+        ChargerStatus::ALL.into_iter()
+            .find(|status| status.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| format!("Unknown charger status '{}'. Expected one of: available, charging, faulted, offline, maintenance, reserved, unknown.", name))
+    }
+}
+
+/// Set of charger statuses that count as up for uptime.
+///
+/// Reports with a `true`/`false` up status are not affected by it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UpStatuses {
+    bits: u8
+}
+
+impl UpStatuses {
+
+    /// Creates a set of the given statuses.
+    pub fn new(statuses: &[ChargerStatus]) -> UpStatuses {
+        UpStatuses { bits: statuses.iter().fold(0, |bits, status| bits | status.bit()) }
+    }
+
+    /// Whether `status` counts as up.
+    pub fn contains(&self, status: ChargerStatus) -> bool {
+        self.bits & status.bit() != 0
+    }
+
+    /// Statuses in the set, in order.
+    pub fn statuses(&self) -> Vec<ChargerStatus> {
+        ChargerStatus::ALL.into_iter().filter(|status| self.contains(*status)).collect()
+    }
+
+    /// Sets the `up` status of a report from its charger status, if it has one.
+    pub fn apply(&self, mut time_range: TimeRange) -> TimeRange {
+        if let Some(status) = time_range.status {
+            time_range.up = self.contains(status);
+        }
+        time_range
+    }
+
+    /// Sets the `up` status of reports from their charger statuses. Reports
+    /// are only copied if any of them changes.
+    pub(crate) fn apply_all<'a>(&self, reports: &'a [TimeRange]) -> Cow<'a, [TimeRange]> {
+        if reports.iter().all(|report| report.status.is_none_or(|status| self.contains(status) == report.up)) {
+            return Cow::Borrowed(reports);
+        }
+        Cow::Owned(reports.iter().cloned().map(|report| self.apply(report)).collect())
+    }
+}
+
+impl Default for UpStatuses {
+    /// `available`, `charging` and `reserved`: the charger works, whether or
+    /// not it's in use.
+    fn default() -> UpStatuses {
+        UpStatuses::new(&[ChargerStatus::Available, ChargerStatus::Charging, ChargerStatus::Reserved])
+    }
+}
+
+impl fmt::Display for UpStatuses {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let names: Vec<&str> = self.statuses().iter().map(ChargerStatus::name).collect();
+        write!(f, "{}", names.join(","))
+    }
+}
+
+impl FromStr for UpStatuses {
+    type Err = String;
+
+    /// Parses a comma separated list of status names, such as `available,charging`.
+    fn from_str(names: &str) -> Result<UpStatuses, String> {
+        let statuses = names.split(',')
+            .map(|name| name.trim().parse::<ChargerStatus>())
+            .collect::<Result<Vec<ChargerStatus>, String>>()?;
+        Ok(UpStatuses::new(&statuses))
+    }
+}
+
+/// Time spent in each charger status, over the windows that count towards the
+/// charger's uptime.
+///
+/// Each part of `kept_times` takes the status of the latest report in input
+/// order that covers it and agrees with its resolved `up` status. Time covered
+/// only by reports without a status is left out, so the durations add up to at
+/// most the time covered by `kept_times`.
+///
+/// ### Input:
+/// - `reports`: `TimeRange`s of a single charger, in input order, with up
+///   statuses applied
+/// - `kept_times`: Sorted, non-overlapping windows combined from `reports`,
+///   with conflicts resolved and excluded time cut out
+///
+/// ### Output:
+/// - `BTreeMap<ChargerStatus, u64>`: Nanoseconds by status, for each status
+///   with any time.
+pub(crate) fn status_durations(reports: &[TimeRange], kept_times: &[TimeRange]) -> BTreeMap<ChargerStatus, u64> {
+
+    // Boundaries of reports with a status, as (time, is start, index in input order)
+    let mut boundaries: Vec<(u64, bool, usize)> = reports.iter().enumerate()
+        .filter(|(_, report)| report.status.is_some() && report.from < report.to)
+        .flat_map(|(index, report)| [(report.from, true, index), (report.to, false, index)])
+        .collect();
+    boundaries.sort();
+    let mut times: Vec<u64> = boundaries.iter()
+        .map(|(time, _, _)| *time)
+        .chain(kept_times.iter().flat_map(|kept| [kept.from, kept.to]))
+        .collect();
+    times.sort();
+    times.dedup();
+
+    let mut durations: BTreeMap<ChargerStatus, u64> = BTreeMap::new();
+    let mut active: BTreeSet<usize> = BTreeSet::new();
+    let (mut next_boundary, mut next_kept) = (0, 0);
+    for (&time, &next_time) in times.iter().zip(times.iter().skip(1)) {
+        while let Some(&(_, is_start, index)) = boundaries.get(next_boundary).filter(|(boundary_time, _, _)| *boundary_time == time) {
+            if is_start {
+                active.insert(index);
+            } else {
+                active.remove(&index);
+            }
+            next_boundary += 1;
+        }
+        while kept_times.get(next_kept).is_some_and(|kept| kept.to <= time) {
+            next_kept += 1;
+        }
+        let Some(kept) = kept_times.get(next_kept).filter(|kept| kept.from <= time) else {
+            continue;
+        };
+        let status = active.iter().rev()
+            .map(|index| &reports[*index])
+            .find(|report| report.up == kept.up)
+            .and_then(|report| report.status);
+        if let Some(status) = status {
+            *durations.entry(status).or_default() += next_time - time;
+        }
+    }
+    durations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_names() {
+        for status in ChargerStatus::ALL {
+            assert_eq!(status.name().parse::<ChargerStatus>(), Ok(status));
+        }
+        assert_eq!("Faulted".parse::<ChargerStatus>(), Ok(ChargerStatus::Faulted));
+        assert!("broken".parse::<ChargerStatus>().is_err());
+    }
+
+    #[test]
+    fn up_statuses() {
+        let up_statuses = "available, Charging".parse::<UpStatuses>().unwrap();
+        assert_eq!(up_statuses.statuses(), vec![ChargerStatus::Available, ChargerStatus::Charging]);
+        assert_eq!(up_statuses.to_string(), "available,charging");
+        assert!(!up_statuses.contains(ChargerStatus::Reserved));
+        assert!(UpStatuses::default().contains(ChargerStatus::Reserved));
+        assert!("available,".parse::<UpStatuses>().is_err());
+
+        let reports = vec![TimeRange::with_status(0, 10, ChargerStatus::Reserved), TimeRange::new(10, 20, true)];
+        assert!(matches!(UpStatuses::default().apply_all(&reports), Cow::Borrowed(_)));
+        let applied = up_statuses.apply_all(&reports);
+        assert_eq!(applied.iter().map(|report| report.up).collect::<Vec<bool>>(), vec![false, true]);
+    }
+
+    #[test]
+    fn durations_by_status() {
+        let reports = vec![
+            TimeRange::with_status(50, 100, ChargerStatus::Faulted),
+            TimeRange::with_status(0, 40, ChargerStatus::Available),
+            TimeRange::with_status(20, 60, ChargerStatus::Charging),
+            TimeRange::new(100, 200, true),
+        ];
+        let reports: Vec<TimeRange> = reports.into_iter().map(|report| UpStatuses::default().apply(report)).collect();
+        // The conflict from 50 to 60 resolved as down, and 150 to 170 excluded
+        let kept_times = vec![
+            TimeRange::new(0, 50, true),
+            TimeRange::new(50, 100, false),
+            TimeRange::new(100, 150, true),
+            TimeRange::new(170, 200, true),
+        ];
+        assert_eq!(status_durations(&reports, &kept_times), BTreeMap::from([
+            (ChargerStatus::Available, 20),
+            (ChargerStatus::Charging, 30),
+            (ChargerStatus::Faulted, 50),
+        ]));
+    }
+}
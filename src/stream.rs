@@ -0,0 +1,721 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::{BufRead, Error};
+use std::mem;
+use std::str::FromStr;
+
+use crate::availability::{apply_window_with, station_uptime, ComputeOptions, TimelineSummary};
+use crate::conflict::combine_with_policy;
+use crate::error::UptimeError;
+use crate::exclusion::{Exclusion, ExclusionIndex, ExclusionSet, StationExclusions};
+use crate::fleet::Fleet;
+use crate::metric::StationMetric;
+use crate::parser::{input_name, line_location, open_input, parse_line, read_each_file, InputKind, LineContent, LineErrors, ReadOptions};
+use crate::report::{ChargerUptime, ExcludedTime, OrphanedCharger, UptimeReport};
+use crate::status::{status_durations, ChargerStatus};
+use crate::time_range::TimeRange;
+use crate::window::{ReportingWindow, UnreportedTime};
+
+/// Order of the availability reports of an input read by `UptimeStream`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StreamOrder {
+    /// All reports are in ascending order of start time, whatever the charger.
+    #[default]
+    Time,
+    /// The reports of each charger are on consecutive lines, in ascending
+    /// order of start time.
+    Charger
+}
+
+impl StreamOrder {
+
+    /// Name of the order, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            StreamOrder::Time => "time",
+            StreamOrder::Charger => "charger",
+        }
+    }
+}
+
+impl fmt::Display for StreamOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for StreamOrder {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<StreamOrder, String> {
+        match name {
+            "time" => Ok(StreamOrder::Time),
+            "charger" => Ok(StreamOrder::Charger),
+            _ => Err(format!("Unknown stream order '{}'. Expected one of: time, charger.", name)),
+        }
+    }
+}
+
+/// Computes station uptime while reading sorted input, without holding every
+/// report in memory like a `Fleet` does.
+///
+/// Stations and exclusions have to be listed before the first report. Each report is then
+/// added to the running totals of its charger and station as it's read.
+/// Overlapping reports of a charger are held until a report starts after all
+/// of them end, so memory is bounded by the number of chargers and of
+/// windows still open, not by the number of reports. In `StreamOrder::Charger`,
+/// the up windows of a station are kept until the end of the input, since
+/// reports of its other chargers can still overlap them.
+///
+/// The result is the same as `Fleet::compute_with_options` over the same input,
+/// except that uptime series, power-weighted uptime and station metrics other
+/// than `StationMetric::AnyUp` aren't supported.
+#[derive(Debug)]
+pub struct UptimeStream {
+    order: StreamOrder,
+    options: ComputeOptions,
+    /// Stations and exclusions read so far, and lines skipped in lenient mode.
+    /// Reports are not added to it.
+    fleet: Fleet,
+    /// Excluded time of each station, set when the first report is added.
+    exclusions: HashMap<u32, StationExclusions>,
+    chargers: HashMap<u32, ChargerStream>,
+    stations: HashMap<u32, StationStream>,
+    /// Charger of the previous report, and its start time.
+    previous_report: Option<(u32, u64)>
+}
+
+/// Running totals of a charger.
+#[derive(Debug, Default)]
+struct ChargerStream {
+    /// Station of the charger, or `None` for an orphaned charger.
+    station_id: Option<u32>,
+    /// Reports overlapping each other that are not combined yet, in input order.
+    open_reports: Vec<TimeRange>,
+    /// End of the latest of `open_reports`.
+    open_until: u64,
+    /// Combined reports of the charger, or raw reports of an orphaned charger.
+    timeline: TimelineStream,
+    /// Time spent in each charger status, like `status_durations`.
+    statuses: BTreeMap<ChargerStatus, u64>,
+    report_count: usize,
+    conflicts: usize
+}
+
+/// Running totals of a station.
+#[derive(Debug, Default)]
+struct StationStream {
+    timeline: TimelineStream,
+    /// Start times of the open reports of the station's chargers, with the
+    /// number of chargers whose open reports start then.
+    open_starts: BTreeMap<u64, usize>
+}
+
+/// Running summary of windows added in any order, like `summarize_timeline`
+/// computes for a sorted list.
+#[derive(Debug, Default)]
+struct TimelineStream {
+    first_reported_time: Option<u64>,
+    last_reported_time: u64,
+    up: WindowUnion,
+    covered: WindowUnion
+}
+
+/// Union of windows added in any order, held as disjoint windows by start
+/// time. Windows ending before a settled time are folded into a total.
+#[derive(Debug, Default)]
+struct WindowUnion {
+    settled: u64,
+    windows: BTreeMap<u64, u64>
+}
+
+impl UptimeStream {
+
+    /// Creates an empty `UptimeStream` for input in `order`.
+    pub fn new(order: StreamOrder, options: ComputeOptions) -> UptimeStream {
+        UptimeStream {
+            order,
+            options,
+            fleet: Fleet::new(),
+            exclusions: HashMap::new(),
+            chargers: HashMap::new(),
+            stations: HashMap::new(),
+            previous_report: None,
+        }
+    }
+
+    /// Reads several files one after the other, like `Fleet::from_files_with_options`.
+    /// Reports have to be in order across files, as if they were one input.
+    pub fn read_files(&mut self, file_paths: &[String], options: &ReadOptions) -> Result<(), UptimeError> {
+        read_each_file(file_paths, options, |file_path| self.read_file(file_path, options))
+    }
+
+    /// Reads a file in the `[Stations]` / `[Charger Availability Reports]`
+    /// format, or `stdin` if the path is `-`.
+    /// ### Input:
+    /// - `file_path`: A string reference to file path
+    /// - `options`: Options for handling invalid lines
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError` in case of error. Error
+    ///   locations carry `file_path`, or `<stdin>`.
+    pub fn read_file(&mut self, file_path: &str, options: &ReadOptions) -> Result<(), UptimeError> {
+        let rejected_before = self.fleet.rejected_lines().len();
+        let reader = open_input(file_path)?;
+        self.read_lines(reader.lines(), options)
+            .map_err(|uptime_error| uptime_error.with_file(input_name(file_path)))?;
+        self.fleet.set_rejected_file(rejected_before, input_name(file_path));
+        Ok(())
+    }
+
+    /// Reads input lines in the `[Stations]` / `[Charger Availability Reports]`
+    /// format, with the same validation as `construct_maps`. Lines out of
+    /// order are invalid lines, and are skipped in lenient mode.
+    /// ### Input:
+    /// - `lines`: An iterator of lines
+    /// - `options`: Options for handling invalid lines
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError` in case of error. Errors that
+    ///   are not in a single line, such as conflicting reports, always stop reading.
+    pub fn read_lines<I>(&mut self, lines: I, options: &ReadOptions) -> Result<(), UptimeError>
+        where I: Iterator<Item = Result<String, Error>> {
+
+        let mut currently_reading: InputKind = InputKind::None;
+        let mut line_errors = LineErrors::new(options);
+        for (line_index, wrapped_line) in lines.enumerate() {
+            let l = wrapped_line?;
+            let line_number = line_index + 1;
+            let line_result = parse_line(&mut currently_reading, line_number, &l)
+                .and_then(|line_content| match line_content {
+                    LineContent::Blank => Ok(()),
+                    LineContent::Station { station_id, chargers, .. } => self.add_station(station_id, &chargers),
+                    LineContent::Report { charger_id, time_range } => self.add_report(charger_id, time_range),
+                    LineContent::ConnectorReport { charger_id, connector_id, .. } => Err(UptimeError::InvalidArguments {
+                        message: format!("Report of connector {}:{} can't be read in streaming mode. Connector reports are only supported without '--stream'.", charger_id, connector_id),
+                    }),
+                    LineContent::Exclusion(exclusion) => self.add_exclusion(exclusion),
+                    LineContent::ChargerAttributes { charger_id, .. } => Err(UptimeError::InvalidArguments {
+                        message: format!("Attributes of charger {} can't be read in streaming mode. Power-weighted uptime is only supported without '--stream'.", charger_id),
+                    }),
+                })
+                .map_err(|line_error| at_line(line_error, line_number, &l));
+            match line_result {
+                Ok(()) => {},
+                Err(line_error) if line_error.location().is_none() => return Err(line_error),
+                Err(line_error) => line_errors.handle(&mut self.fleet, line_error)?,
+            }
+        }
+        line_errors.finish()
+    }
+
+    /// Adds chargers to a station, like `Fleet::add_station`.
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError::UnsortedInput` if a report was
+    ///   already added, and `UptimeError::ChargerMappedToMultipleStations` if any
+    ///   of the chargers is already mapped to a different station.
+    pub fn add_station(&mut self, station_id: u32, chargers: &[u32]) -> Result<(), UptimeError> {
+        if self.previous_report.is_some() {
+            return Err(UptimeError::UnsortedInput {
+                location: None,
+                message: format!("Station {} is listed after availability reports. Streaming needs all stations before the first report.", station_id),
+            });
+        }
+        self.fleet.add_station(station_id, chargers)
+    }
+
+    /// Adds a window of excused downtime, like `Fleet::add_exclusion`.
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError::UnsortedInput` if a report was
+    ///   already added.
+    pub fn add_exclusion(&mut self, exclusion: Exclusion) -> Result<(), UptimeError> {
+        if self.previous_report.is_some() {
+            return Err(UptimeError::UnsortedInput {
+                location: None,
+                message: format!("Exclusion of {} is listed after availability reports. Streaming needs all exclusions before the first report.", exclusion.target),
+            });
+        }
+        self.fleet.add_exclusion(exclusion);
+        Ok(())
+    }
+
+    /// Adds an availability report of a charger to the running totals.
+    /// ### Input:
+    /// - `charger_id`: ID of the reporting charger
+    /// - `time_range`: The reported window and up/down status
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError::UnsortedInput` if the report is
+    ///   out of order, and the report is not added. `UptimeError::ConflictingReports`
+    ///   if it closes overlapping reports that the conflict policy doesn't resolve.
+    ///   `UptimeError::InvalidArguments` if the station metric isn't supported.
+    pub fn add_report(&mut self, charger_id: u32, time_range: TimeRange) -> Result<(), UptimeError> {
+        let time_range = self.options.up_statuses.apply(time_range);
+        match (self.order, self.previous_report) {
+            (_, None) => {
+                if self.options.station_metric != StationMetric::AnyUp {
+                    return Err(UptimeError::InvalidArguments {
+                        message: format!("Station metric '{}' can't be computed in streaming mode. Only 'any-up' is supported with '--stream'.", self.options.station_metric),
+                    });
+                }
+                // Stations and exclusions are all known by the first report
+                let exclusion_index = ExclusionIndex::new(self.fleet.exclusions());
+                self.exclusions = self.fleet.station_charger_map().iter()
+                    .map(|(station_id, chargers)| (*station_id, exclusion_index.station(*station_id, chargers)))
+                    .collect();
+            },
+            (StreamOrder::Charger, Some((previous_charger, _))) if previous_charger != charger_id => {
+                if self.chargers.contains_key(&charger_id) {
+                    return Err(UptimeError::UnsortedInput {
+                        location: None,
+                        message: format!("Reports of charger {} are not on consecutive lines. Streaming by charger needs the reports of each charger together.", charger_id),
+                    });
+                }
+                // The previous charger has no more reports
+                self.close_open_reports(previous_charger)?;
+            },
+            (_, Some((_, previous_from))) if time_range.from < previous_from => {
+                return Err(UptimeError::UnsortedInput {
+                    location: None,
+                    message: format!("Report of charger {} starts at {}, before the previous report at {}. Streaming by {} needs reports in ascending order of start time.",
+                        charger_id, time_range.from, previous_from, self.order),
+                });
+            },
+            _ => {},
+        }
+        self.previous_report = Some((charger_id, time_range.from));
+
+        let station_id = self.fleet.station_of(charger_id);
+        let charger = self.chargers.entry(charger_id).or_insert_with(|| ChargerStream { station_id, ..ChargerStream::default() });
+        charger.report_count += 1;
+        if station_id.is_none() {
+            // Orphaned chargers only count their reported time
+            charger.timeline.add(&time_range);
+            charger.timeline.settle(time_range.from);
+            return Ok(());
+        }
+
+        if !charger.open_reports.is_empty() && time_range.from >= charger.open_until {
+            self.close_open_reports(charger_id)?;
+        }
+        let charger = self.chargers.get_mut(&charger_id).unwrap();
+        let station = self.stations.entry(station_id.unwrap()).or_default();
+        if charger.open_reports.is_empty() {
+            *station.open_starts.entry(time_range.from).or_default() += 1;
+            charger.open_until = time_range.to;
+        }
+        charger.open_until = charger.open_until.max(time_range.to);
+        charger.open_reports.push(time_range.clone());
+
+        if self.order == StreamOrder::Time {
+            // Later reports of the station's chargers start after both
+            let open_from = station.open_starts.keys().next().copied().unwrap_or(time_range.from);
+            station.timeline.settle(open_from.min(time_range.from));
+        }
+        Ok(())
+    }
+
+    /// Stations read so far, and input lines skipped in lenient mode.
+    pub fn fleet(&self) -> &Fleet {
+        &self.fleet
+    }
+
+    /// Chargers with availability reports that are not listed under any
+    /// station, in ascending order of Charger ID, like `Fleet::orphaned_chargers`.
+    pub fn orphaned_chargers(&self) -> Vec<OrphanedCharger> {
+        let mut orphaned_chargers: Vec<OrphanedCharger> = self.chargers.iter()
+            .filter(|(_, charger)| charger.station_id.is_none())
+            .map(|(charger_id, charger)| OrphanedCharger {
+                charger_id: *charger_id,
+                report_count: charger.report_count,
+                reported_nanos: charger.timeline.covered.total(),
+            })
+            .collect();
+        orphaned_chargers.sort_by_key(|orphaned_charger| orphaned_charger.charger_id);
+        orphaned_chargers
+    }
+
+    /// Fails with `UptimeError::OrphanedChargers` if any charger with
+    /// availability reports is not listed under a station.
+    pub fn check_orphaned_chargers(&self) -> Result<(), UptimeError> {
+        let orphaned_chargers = self.orphaned_chargers();
+        if orphaned_chargers.is_empty() {
+            return Ok(());
+        }
+        Err(UptimeError::OrphanedChargers {
+            charger_ids: orphaned_chargers.iter().map(|orphaned_charger| orphaned_charger.charger_id).collect(),
+        })
+    }
+
+    /// Combines the reports still open, and computes the uptime of every station.
+    /// ### Output:
+    /// - `Result<UptimeReport, UptimeError>`: The report wrapped in `Ok()` if successful
+    ///   and `UptimeError` in case of conflicting reports.
+    pub fn finish(mut self) -> Result<UptimeReport, UptimeError> {
+
+        let open_chargers: Vec<u32> = self.chargers.iter()
+            .filter(|(_, charger)| !charger.open_reports.is_empty())
+            .map(|(charger_id, _)| *charger_id)
+            .collect();
+        for charger_id in open_chargers {
+            self.close_open_reports(charger_id)?;
+        }
+
+        let window = self.options.window.as_ref();
+        let no_exclusions = StationExclusions::default();
+        let mut station_availability = Vec::new();
+        let mut charger_availability = Vec::new();
+        let mut excluded = Vec::new();
+        let mut conflicts_resolved: BTreeMap<u32, usize> = BTreeMap::new();
+        for (station_id, chargers) in self.fleet.station_charger_map() {
+            let station_exclusions = self.exclusions.get(station_id).unwrap_or(&no_exclusions);
+            for charger_id in chargers {
+                let Some(charger) = self.chargers.get(charger_id) else {
+                    continue;
+                };
+                if charger.conflicts>0 {
+                    conflicts_resolved.insert(*charger_id, charger.conflicts);
+                }
+                if let Some(charger_summary) = charger.timeline.summary(window, station_exclusions.charger(*charger_id)) {
+                    charger_availability.push(ChargerUptime {
+                        charger_id: *charger_id,
+                        station_id: *station_id,
+                        uptime_percent: (!charger_summary.is_all_excluded()).then(|| charger_summary.uptime_percent(self.options.percent_format)),
+                        reported_nanos: charger_summary.reported_time,
+                        up_nanos: charger_summary.up_time,
+                        status_nanos: charger.statuses.clone(),
+                        excluded_nanos: charger_summary.excluded_time,
+                    });
+                }
+            }
+            let station_summary = self.stations.get(station_id)
+                .and_then(|station| station.timeline.summary(window, &station_exclusions.station))
+                .or_else(|| window.filter(|window| window.unreported == UnreportedTime::Down).map(|window| {
+                    // Down all of the window, like `compute_availability` counts it
+                    let summary = TimelineSummary {
+                        first_reported_time: window.from,
+                        last_reported_time: window.to,
+                        reported_time: window.duration(),
+                        up_time: 0,
+                        excluded_time: 0,
+                    };
+                    station_exclusions.station.exclude_span(summary, Some(window))
+                }));
+            if let Some(summary) = &station_summary {
+                excluded.extend(station_exclusions.station.reasons_within(summary.first_reported_time, summary.last_reported_time).into_iter()
+                    .map(|(reason, excluded_nanos)| ExcludedTime { station_id: *station_id, reason, excluded_nanos }));
+            }
+            station_availability.extend(station_uptime(*station_id, station_summary.as_ref(), chargers.len(), &self.options));
+        }
+        let mut report = UptimeReport::new(station_availability);
+        report.set_chargers(charger_availability);
+        report.set_excluded(excluded);
+        report.conflicts_resolved = conflicts_resolved;
+        Ok(report)
+    }
+
+    /// Combines the open reports of a charger with the conflict policy, and
+    /// adds them to the running totals of the charger and its station. The
+    /// reported span counts excluded time, but up time and covered time don't.
+    fn close_open_reports(&mut self, charger_id: u32) -> Result<(), UptimeError> {
+
+        let Some(charger) = self.chargers.get_mut(&charger_id) else {
+            return Ok(());
+        };
+        let Some(station_id) = charger.station_id else {
+            return Ok(());
+        };
+        if charger.open_reports.is_empty() {
+            return Ok(());
+        }
+        let open_reports = mem::take(&mut charger.open_reports);
+        let (mut combined, conflicts) = combine_with_policy(charger_id, &open_reports, self.options.conflict_policy)?;
+        charger.conflicts += conflicts;
+        if let Some(window) = &self.options.window {
+            combined = window.clip(&combined);
+        }
+
+        let station = self.stations.entry(station_id).or_default();
+        // Open reports are sorted, so the first one starts first
+        let open_from = open_reports[0].from;
+        if let Some(open_count) = station.open_starts.get_mut(&open_from) {
+            *open_count -= 1;
+            if *open_count == 0 {
+                station.open_starts.remove(&open_from);
+            }
+        }
+        for time_range in &combined {
+            charger.timeline.add_span(time_range.from, time_range.to);
+            station.timeline.add_span(time_range.from, time_range.to);
+        }
+        let kept = match self.exclusions.get(&station_id) {
+            Some(station_exclusions) => station_exclusions.charger(charger_id).remove_from(combined),
+            None => combined,
+        };
+        for time_range in &kept {
+            charger.timeline.add_covered(time_range);
+            station.timeline.add_covered(time_range);
+        }
+        // Open reports don't overlap any other reports of the charger
+        for (status, nanos) in status_durations(&open_reports, &kept) {
+            *charger.statuses.entry(status).or_default() += nanos;
+        }
+        // Later reports of the charger start after the open ones end
+        charger.timeline.settle(charger.open_until);
+        Ok(())
+    }
+}
+
+/// Sets the location of an error from adding a line's content, which is
+/// not known when adding it.
+fn at_line(mut line_error: UptimeError, line_number: usize, l: &str) -> UptimeError {
+    match &mut line_error {
+        UptimeError::ChargerMappedToMultipleStations { location, .. } | UptimeError::UnsortedInput { location, .. }
+            if location.is_none() => *location = Some(line_location(line_number, l)),
+        _ => {},
+    }
+    line_error
+}
+
+impl TimelineStream {
+
+    /// Adds a window to the summary.
+    fn add(&mut self, time_range: &TimeRange) {
+        self.add_span(time_range.from, time_range.to);
+        self.add_covered(time_range);
+    }
+
+    /// Widens the reported span to include `[from, to)`.
+    fn add_span(&mut self, from: u64, to: u64) {
+        self.first_reported_time = Some(self.first_reported_time.map_or(from, |first| first.min(from)));
+        self.last_reported_time = self.last_reported_time.max(to);
+    }
+
+    /// Adds a window to the covered and up time, without widening the span.
+    fn add_covered(&mut self, time_range: &TimeRange) {
+        self.covered.insert(time_range.from, time_range.to);
+        if time_range.up {
+            self.up.insert(time_range.from, time_range.to);
+        }
+    }
+
+    /// Folds windows ending by `time` into the totals. No window starting
+    /// before `time` may be added after this.
+    fn settle(&mut self, time: u64) {
+        self.up.settle(time);
+        self.covered.settle(time);
+    }
+
+    /// Summary of the windows added so far, with the reporting window applied
+    /// like `apply_window` does, and excluded time taken out of it.
+    /// ### Output:
+    /// - `Option<TimelineSummary>`: The summary, or `None` if no window was added.
+    fn summary(&self, window: Option<&ReportingWindow>, exclusions: &ExclusionSet) -> Option<TimelineSummary> {
+        let first_reported_time = self.first_reported_time?;
+        let summary = TimelineSummary {
+            first_reported_time,
+            last_reported_time: self.last_reported_time,
+            reported_time: self.last_reported_time - first_reported_time,
+            up_time: self.up.total(),
+            excluded_time: 0,
+        };
+        Some(exclusions.exclude_span(apply_window_with(summary, window, || self.covered.total()), window))
+    }
+}
+
+impl WindowUnion {
+
+    /// Adds the window `[from, to)`, merging it with the windows it overlaps
+    /// or touches.
+    fn insert(&mut self, mut from: u64, mut to: u64) {
+        if from >= to {
+            return;
+        }
+        if let Some((&start, &end)) = self.windows.range(..=from).next_back() {
+            if end >= from {
+                from = start;
+                to = to.max(end);
+                self.windows.remove(&start);
+            }
+        }
+        while let Some((&start, &end)) = self.windows.range(from..).next() {
+            if start > to {
+                break;
+            }
+            to = to.max(end);
+            self.windows.remove(&start);
+        }
+        self.windows.insert(from, to);
+    }
+
+    /// Folds the windows ending by `time` into the settled total.
+    fn settle(&mut self, time: u64) {
+        while let Some((&start, &end)) = self.windows.first_key_value() {
+            if end > time {
+                break;
+            }
+            self.settled += end - start;
+            self.windows.remove(&start);
+        }
+    }
+
+    /// Time covered by the union.
+    fn total(&self) -> u64 {
+        self.settled + self.windows.iter().map(|(start, end)| end - start).sum::<u64>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::availability::NoDataPolicy;
+    use crate::conflict::ConflictPolicy;
+
+    const SORTED_BY_TIME: &str = "\
+[Stations]
+0 1001 1002
+1 1003
+2 1004
+
+[Charger Availability Reports]
+1001 0 50000 true
+1003 0 50000 false
+1004 0 50000 true
+1002 20000 60000 true
+1001 50000 100000 true
+1003 50000 100000 true
+1002 70000 80000 false
+1004 100000 200000 true
+";
+
+    fn batch_report(input: &str, options: &ComputeOptions) -> UptimeReport {
+        Fleet::from_reader(input.as_bytes()).unwrap().compute_with_options(options).unwrap()
+    }
+
+    fn stream_report(input: &str, order: StreamOrder, options: &ComputeOptions) -> Result<UptimeReport, UptimeError> {
+        let mut stream = UptimeStream::new(order, options.clone());
+        stream.read_lines(input.as_bytes().lines(), &ReadOptions::default())?;
+        stream.finish()
+    }
+
+    #[test]
+    fn matches_batch_computation() {
+        let mut options = ComputeOptions::default();
+        let stream = stream_report(SORTED_BY_TIME, StreamOrder::Time, &options).unwrap();
+        assert_eq!(stream.to_string(), "0 100\n1 50\n2 75");
+        assert_eq!(stream, batch_report(SORTED_BY_TIME, &options));
+
+        options.window = Some(ReportingWindow::new(30000, 90000, UnreportedTime::Unknown).unwrap());
+        options.conflict_policy = ConflictPolicy::DownWins;
+        let stream = stream_report(SORTED_BY_TIME, StreamOrder::Time, &options).unwrap();
+        assert_eq!(stream, batch_report(SORTED_BY_TIME, &options));
+
+        // Station 2 has no reports in the window, and was down all of it
+        options.window = Some(ReportingWindow::new(30000, 90000, UnreportedTime::Down).unwrap());
+        let stream = stream_report(SORTED_BY_TIME, StreamOrder::Time, &options).unwrap();
+        assert_eq!(stream, batch_report(SORTED_BY_TIME, &options));
+        assert_eq!(stream.get(2).unwrap().reported_nanos, 60000);
+    }
+
+    #[test]
+    fn charger_statuses_match_batch() {
+        let input = "[Stations]\n0 1001 1002\n\n[Charger Availability Reports]\n\
+            1001 0 50 available\n1002 0 30 faulted\n1002 30 90 reserved\n1001 50 80 charging\n1001 80 100 offline\n";
+        let options = ComputeOptions {
+            up_statuses: "available".parse().unwrap(),
+            window: Some(ReportingWindow::new(20, 100, UnreportedTime::Down).unwrap()),
+            ..ComputeOptions::default()
+        };
+        let report = stream_report(input, StreamOrder::Time, &options).unwrap();
+        assert_eq!(report, batch_report(input, &options));
+        assert_eq!(report.charger_lines(), "1001 0 37 80 30 available=30 charging=30 offline=20\n1002 0 0 80 0 faulted=10 reserved=60");
+
+        // Durations follow the resolved conflict and leave excluded time out,
+        // so they add up to the reported time
+        let input = "[Stations]\n0 1001\n\n[Exclusions]\ncharger 1001 10 20 maintenance\n\n\
+            [Charger Availability Reports]\n1001 0 50 available\n1001 30 60 faulted\n";
+        let options = ComputeOptions { conflict_policy: ConflictPolicy::DownWins, ..ComputeOptions::default() };
+        let report = stream_report(input, StreamOrder::Time, &options).unwrap();
+        assert_eq!(report, batch_report(input, &options));
+        assert_eq!(report.charger_lines(), "1001 0 40 50 20 available=20 faulted=30");
+    }
+
+    #[test]
+    fn exclusions_match_batch() {
+        let input = "[Stations]\n0 1001 1002\n1 1003\n\n[Exclusions]\n\
+            station 0 80 100 utility_outage\ncharger 1001 20 40 maintenance\ncharger 1003 0 30 vandalism\n\n\
+            [Charger Availability Reports]\n1001 0 100 true\n1002 0 50 true\n1003 0 60 true\n1002 50 100 false\n1003 60 120 false\n";
+        let mut options = ComputeOptions::default();
+        let report = stream_report(input, StreamOrder::Time, &options).unwrap();
+        assert_eq!(report, batch_report(input, &options));
+        assert_eq!(report.excluded_lines(), "0 utility_outage 20\n1 vandalism 30");
+
+        options.window = Some(ReportingWindow::new(10, 90, UnreportedTime::Unknown).unwrap());
+        let report = stream_report(input, StreamOrder::Time, &options).unwrap();
+        assert_eq!(report, batch_report(input, &options));
+
+        let late_exclusion = format!("{}[Exclusions]\nstation 1 0 10 maintenance\n", input);
+        assert!(matches!(stream_report(&late_exclusion, StreamOrder::Time, &options), Err(UptimeError::UnsortedInput { .. })));
+
+        // An excluded charger leaves the time of the others in the station
+        let input = "[Stations]\n0 1001 1002\n\n[Exclusions]\ncharger 1002 0 100 maintenance\n\n\
+            [Charger Availability Reports]\n1001 0 100 true\n1002 0 100 false\n";
+        let options = ComputeOptions::default();
+        let report = stream_report(input, StreamOrder::Time, &options).unwrap();
+        assert_eq!(report, batch_report(input, &options));
+        assert_eq!(report.to_string(), "0 100");
+
+        // All of the reported time excluded
+        let input = input.replace("charger 1002", "station 0");
+        let options = ComputeOptions { no_data_policy: NoDataPolicy::Unknown, ..ComputeOptions::default() };
+        let report = stream_report(&input, StreamOrder::Time, &options).unwrap();
+        assert_eq!(report, batch_report(&input, &options));
+        assert_eq!(report.to_string(), "0 unknown");
+    }
+
+    #[test]
+    fn sorted_by_charger() {
+        let input = "[Stations]\n0 1001 1002\n[Charger Availability Reports]\n\
+            1002 50 150 true\n1002 100 300 false\n1001 0 100 true\n1001 90 120 true\n1005 0 10 true\n";
+        let options = ComputeOptions { conflict_policy: ConflictPolicy::UpWins, ..ComputeOptions::default() };
+        let stream = stream_report(input, StreamOrder::Charger, &options).unwrap();
+        assert_eq!(stream, batch_report(input, &options));
+        assert_eq!(stream.conflicts_resolved, BTreeMap::from([(1002, 1)]));
+    }
+
+    #[test]
+    fn unsorted_input_is_an_error() {
+        let options = ComputeOptions::default();
+        let unsorted = "[Stations]\n0 1001 1002\n[Charger Availability Reports]\n1001 50 100 true\n1002 0 50 true\n";
+        match stream_report(unsorted, StreamOrder::Time, &options) {
+            Err(UptimeError::UnsortedInput { location: Some(location), .. }) => assert_eq!(location.line, 5),
+            other => panic!("Expected unsorted input, got {:?}", other),
+        }
+        assert!(stream_report(unsorted, StreamOrder::Charger, &options).is_ok());
+
+        let ungrouped = "[Stations]\n0 1001 1002\n[Charger Availability Reports]\n1001 0 50 true\n1002 0 50 true\n1001 50 100 true\n";
+        assert!(matches!(stream_report(ungrouped, StreamOrder::Charger, &options), Err(UptimeError::UnsortedInput { .. })));
+        let late_station = "[Charger Availability Reports]\n1001 0 50 true\n[Stations]\n0 1001\n";
+        assert!(matches!(stream_report(late_station, StreamOrder::Time, &options), Err(UptimeError::UnsortedInput { .. })));
+    }
+
+    #[test]
+    fn charger_attributes_are_an_error() {
+        // Power-weighted uptime would be missing from the streamed stations
+        let input = "[Stations]\n0 1001\n[Charger Attributes]\n1001 50\n[Charger Availability Reports]\n1001 0 50 true\n";
+        assert!(matches!(stream_report(input, StreamOrder::Time, &ComputeOptions::default()), Err(UptimeError::InvalidArguments { .. })));
+    }
+
+    #[test]
+    fn settled_windows_are_dropped() {
+        let mut union = WindowUnion::default();
+        union.insert(0, 10);
+        union.insert(20, 30);
+        union.insert(5, 20);
+        union.insert(40, 50);
+        assert_eq!(union.windows.len(), 2);
+        union.settle(35);
+        assert_eq!(union.windows.len(), 1);
+        assert_eq!(union.total(), 40);
+    }
+}
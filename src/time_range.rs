@@ -0,0 +1,52 @@
+use crate::status::{ChargerStatus, UpStatuses};
+
+/// A single availability window reported by a charger.
+///
+/// The window is taken as `[from, to)`, where `from` is inclusive and `to`
+/// is exclusive. Both are timestamps in nanoseconds.
+///
+/// Ordering is by `from`, then `to`, then `up`, then `status`, so a sorted
+/// list of `TimeRange`s is in ascending order of start time.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TimeRange {
+    pub from: u64,
+    pub to: u64,
+    pub up: bool,
+    /// Charger status of the report, or `None` for a report with a
+    /// `true`/`false` up status. `up` is set from it by `UpStatuses::apply`.
+    pub status: Option<ChargerStatus>
+}
+
+impl TimeRange {
+
+    /// Creates a new `TimeRange`.
+    /// ### Input:
+    /// - `from`: Start time in nanoseconds (inclusive)
+    /// - `to`: End time in nanoseconds (exclusive)
+    /// - `up`: Whether the charger was available during the window
+    ///
+    /// ### Output:
+    /// - `TimeRange`: The constructed struct, without a charger status. No
+    ///   validation is done here.
+    pub fn new(from: u64, to: u64, up: bool) -> TimeRange {
+        TimeRange { from, to, up, status: None }
+    }
+
+    /// Creates a new `TimeRange` with a charger status.
+    /// ### Input:
+    /// - `from`: Start time in nanoseconds (inclusive)
+    /// - `to`: End time in nanoseconds (exclusive)
+    /// - `status`: Status of the charger during the window
+    ///
+    /// ### Output:
+    /// - `TimeRange`: The constructed struct. It's up if the default
+    ///   `UpStatuses` contain `status`. No validation is done here.
+    pub fn with_status(from: u64, to: u64, status: ChargerStatus) -> TimeRange {
+        TimeRange { from, to, up: UpStatuses::default().contains(status), status: Some(status) }
+    }
+
+    /// Length of the window in nanoseconds.
+    pub fn duration(&self) -> u64 {
+        self.to.saturating_sub(self.from)
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+
+use serde::Serialize;
+
+use crate::error::UptimeError;
+use crate::fleet::Fleet;
+use crate::parser::{input_name, open_input, read_line, InputKind, LineContent};
+use crate::time_range::TimeRange;
+
+/// How serious a validation finding is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// Worth knowing, but uptime is computed as expected.
+    Info,
+    /// Likely a mistake in the data, though uptime can still be computed.
+    Warning,
+    /// Uptime can't be computed from the input as is.
+    Error
+}
+
+impl Severity {
+
+    /// Name of the severity, as written in reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A check run by `Validator`. Each check has a fixed severity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Check {
+    /// A line that `construct_maps` rejects.
+    InvalidLine,
+    /// Overlapping reports of a charger that disagree on `up` status.
+    ConflictingWindows,
+    /// A station listed on more than one line.
+    DuplicateStation,
+    /// A charger listed more than once under the same station.
+    DuplicateCharger,
+    /// A charger listed under a station that has no reports.
+    ChargerWithoutReports,
+    /// A charger with reports that isn't listed under any station.
+    OrphanedCharger,
+    /// A report that starts and ends at the same time.
+    ZeroLengthWindow,
+    /// Overlapping reports of a charger with the same `up` status.
+    OverlappingWindows,
+    /// Time between two reports of a charger that neither covers.
+    ReportGap,
+    /// A report of a charger that starts before its previous report.
+    NonMonotonicOrder
+}
+
+impl Check {
+
+    /// Name of the check, as written in reports.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Check::InvalidLine => "invalid-line",
+            Check::ConflictingWindows => "conflicting-windows",
+            Check::DuplicateStation => "duplicate-station",
+            Check::DuplicateCharger => "duplicate-charger",
+            Check::ChargerWithoutReports => "charger-without-reports",
+            Check::OrphanedCharger => "orphaned-charger",
+            Check::ZeroLengthWindow => "zero-length-window",
+            Check::OverlappingWindows => "overlapping-windows",
+            Check::ReportGap => "report-gap",
+            Check::NonMonotonicOrder => "non-monotonic-order",
+        }
+    }
+
+    /// Severity of the findings of the check.
+    pub fn severity(&self) -> Severity {
+        match self {
+            Check::InvalidLine | Check::ConflictingWindows => Severity::Error,
+            Check::DuplicateStation | Check::DuplicateCharger | Check::ChargerWithoutReports
+                | Check::OrphanedCharger | Check::ZeroLengthWindow | Check::OverlappingWindows => Severity::Warning,
+            Check::ReportGap | Check::NonMonotonicOrder => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A single problem found by a check.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub check: Check,
+    pub severity: Severity,
+    /// Input file of the line the finding is about, or `<stdin>`.
+    pub file: String,
+    /// 1-based line number of the line the finding is about.
+    pub line: usize,
+    pub message: String
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}[{}]: {}", self.file, self.line, self.severity, self.check, self.message)
+    }
+}
+
+/// Result of validating input with a `Validator`.
+///
+/// Findings are grouped by check, in the order of `Check`, then ordered by
+/// file and line.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ValidationReport {
+    pub errors: usize,
+    pub warnings: usize,
+    pub infos: usize,
+    /// Number of findings of each check that found any.
+    pub counts: BTreeMap<&'static str, usize>,
+    pub findings: Vec<Finding>
+}
+
+impl ValidationReport {
+
+    /// Creates a report from findings in any order.
+    pub fn new(mut findings: Vec<Finding>) -> ValidationReport {
+        findings.sort_by(|a, b| (a.check, &a.file, a.line).cmp(&(b.check, &b.file, b.line)));
+        let mut report = ValidationReport::default();
+        for finding in &findings {
+            match finding.severity {
+                Severity::Error => report.errors += 1,
+                Severity::Warning => report.warnings += 1,
+                Severity::Info => report.infos += 1,
+            }
+            *report.counts.entry(finding.check.name()).or_default() += 1;
+        }
+        report.findings = findings;
+        report
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    /// Writes each finding on its own line, followed by a summary of the
+    /// number of findings of each severity and each check.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for finding in &self.findings {
+            writeln!(f, "{}", finding)?;
+        }
+        write!(f, "Summary: {} error(s), {} warning(s), {} info(s)", self.errors, self.warnings, self.infos)?;
+        for (check, count) in &self.counts {
+            write!(f, "\n  {}: {}", check, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// A line of input, by index of the file in `Validator::files` and line number.
+type LineRef = (usize, usize);
+
+/// Validates input in the `[Stations]` / `[Charger Availability Reports]`
+/// format, with the same parsing as `construct_maps`, without computing uptime.
+///
+/// Files are read one after the other, like `Fleet::from_files_with_options`,
+/// and checks that span stations and chargers run across all of them.
+#[derive(Debug, Default)]
+pub struct Validator {
+    fleet: Fleet,
+    files: Vec<String>,
+    findings: Vec<Finding>,
+    /// First line each station is listed on.
+    station_lines: HashMap<u32, LineRef>,
+    /// Chargers listed under each station so far.
+    station_chargers: HashMap<u32, HashSet<u32>>,
+    /// First line each charger is listed on under a station.
+    charger_lines: HashMap<u32, LineRef>,
+    /// Reports of each charger with their lines, in input order.
+    charger_reports: HashMap<u32, Vec<(TimeRange, LineRef)>>,
+    /// First line of a connector report of each charger. Reports of different
+    /// connectors may overlap, so their windows are not checked.
+    connector_report_lines: HashMap<u32, LineRef>
+}
+
+impl Validator {
+
+    /// Creates a validator without any input.
+    pub fn new() -> Validator {
+        Validator::default()
+    }
+
+    /// Validates a file, or `stdin` if the path is `-`.
+    /// ### Input:
+    /// - `file_path`: A string reference to file path
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError::Io` if the file can't be read.
+    ///   Invalid lines are findings, not errors.
+    pub fn read_file(&mut self, file_path: &str) -> Result<(), UptimeError> {
+        let reader = open_input(file_path)?;
+        self.read_lines(input_name(file_path), reader)
+    }
+
+    /// Validates input from a buffered reader.
+    /// ### Input:
+    /// - `name`: Name of the input in findings
+    /// - `reader`: A reader over the input
+    ///
+    /// ### Output:
+    /// - `Result<(), UptimeError>`: `UptimeError::Io` if the input can't be read.
+    pub fn read_lines<R: BufRead>(&mut self, name: &str, reader: R) -> Result<(), UptimeError> {
+        let file_index = self.files.len();
+        self.files.push(name.to_string());
+        let mut currently_reading = InputKind::None;
+        for (line_index, wrapped_line) in reader.lines().enumerate() {
+            let l = wrapped_line?;
+            let line = (file_index, line_index + 1);
+            match read_line(&mut self.fleet, &mut currently_reading, line.1, &l) {
+                Ok(LineContent::Blank) => {},
+                Ok(LineContent::Station { station_id, chargers, .. }) => self.check_station(station_id, &chargers, line),
+                Ok(LineContent::Report { charger_id, time_range }) => self.check_report(charger_id, time_range, line),
+                Ok(LineContent::ConnectorReport { charger_id, .. }) => {
+                    self.connector_report_lines.entry(charger_id).or_insert(line);
+                },
+                Ok(LineContent::Exclusion(_) | LineContent::ChargerAttributes { .. }) => {},
+                Err(line_error) => {
+                    let message = line_error.to_string().replace('\n', " ");
+                    self.push(Check::InvalidLine, line, format!("{}: {}", line_error.category(), message));
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the checks that need all of the input, and returns all findings.
+    pub fn finish(mut self) -> ValidationReport {
+
+        // Chargers without reports, in ascending order for stable output
+        let mut listed_chargers: Vec<(&u32, &LineRef)> = self.charger_lines.iter().collect();
+        listed_chargers.sort();
+        let mut findings: Vec<(Check, LineRef, String)> = Vec::new();
+        for (charger_id, line) in listed_chargers {
+            if !self.charger_reports.contains_key(charger_id) && !self.connector_report_lines.contains_key(charger_id) {
+                findings.push((Check::ChargerWithoutReports, *line,
+                    format!("Charger {} has no availability reports.", charger_id)));
+            }
+        }
+
+        for orphaned_charger in self.fleet.orphaned_chargers() {
+            let first_line = self.charger_reports.get(&orphaned_charger.charger_id)
+                .map_or_else(|| self.connector_report_lines[&orphaned_charger.charger_id], |reports| reports[0].1);
+            findings.push((Check::OrphanedCharger, first_line,
+                format!("Charger {} has {} report(s) covering {} ns, but isn't listed under any station.",
+                    orphaned_charger.charger_id, orphaned_charger.report_count, orphaned_charger.reported_nanos)));
+        }
+
+        for (charger_id, reports) in &self.charger_reports {
+            check_charger_windows(*charger_id, reports, &mut findings);
+        }
+
+        for (check, line, message) in findings {
+            self.push(check, line, message);
+        }
+        ValidationReport::new(self.findings)
+    }
+
+    fn check_station(&mut self, station_id: u32, chargers: &[u32], line: LineRef) {
+        if let Some(first_line) = self.station_lines.get(&station_id).copied() {
+            let message = format!("Station {} is already listed on {}.", station_id, self.describe(first_line, line));
+            self.push(Check::DuplicateStation, line, message);
+        } else {
+            self.station_lines.insert(station_id, line);
+        }
+        for charger_id in chargers {
+            if !self.station_chargers.entry(station_id).or_default().insert(*charger_id) {
+                let first_line = self.charger_lines[charger_id];
+                let message = format!("Charger {} is already listed under station {} on {}.",
+                    charger_id, station_id, self.describe(first_line, line));
+                self.push(Check::DuplicateCharger, line, message);
+            }
+            self.charger_lines.entry(*charger_id).or_insert(line);
+        }
+    }
+
+    fn check_report(&mut self, charger_id: u32, time_range: TimeRange, line: LineRef) {
+        if time_range.from == time_range.to {
+            self.push(Check::ZeroLengthWindow, line,
+                format!("Report of charger {} starts and ends at {}.", charger_id, time_range.from));
+        }
+        let reports = self.charger_reports.entry(charger_id).or_default();
+        if let Some((previous, previous_line)) = reports.last() {
+            if time_range.from < previous.from {
+                let message = format!("Report of charger {} starts at {}, before its previous report on line {} starts at {}.",
+                    charger_id, time_range.from, previous_line.1, previous.from);
+                reports.push((time_range, line));
+                self.push(Check::NonMonotonicOrder, line, message);
+                return;
+            }
+        }
+        reports.push((time_range, line));
+    }
+
+    /// Describes `line` relative to `from_line`: `line 3`, or `line 3 of input.txt`
+    /// if they are in different files.
+    fn describe(&self, line: LineRef, from_line: LineRef) -> String {
+        if line.0 == from_line.0 {
+            format!("line {}", line.1)
+        } else {
+            format!("line {} of {}", line.1, self.files[line.0])
+        }
+    }
+
+    fn push(&mut self, check: Check, line: LineRef, message: String) {
+        self.findings.push(Finding {
+            check,
+            severity: check.severity(),
+            file: self.files[line.0].clone(),
+            line: line.1,
+            message,
+        });
+    }
+}
+
+/// Finds overlapping reports and gaps between reports of a single charger.
+fn check_charger_windows(charger_id: u32, reports: &[(TimeRange, LineRef)], findings: &mut Vec<(Check, LineRef, String)>) {
+
+    let mut sorted_reports: Vec<&(TimeRange, LineRef)> = reports.iter()
+        .filter(|(time_range, _)| time_range.from < time_range.to)
+        .collect();
+    sorted_reports.sort_by_key(|(time_range, line)| (time_range.from, time_range.to, *line));
+
+    // The report reaching furthest so far
+    let mut reaching: Option<&(TimeRange, LineRef)> = None;
+    for report in sorted_reports {
+        let (time_range, line) = report;
+        if let Some((reaching_range, reaching_line)) = reaching {
+            if time_range.from < reaching_range.to {
+                let check = if time_range.up == reaching_range.up { Check::OverlappingWindows } else { Check::ConflictingWindows };
+                findings.push((check, *line, format!("Report of charger {} from {} to {} overlaps its report on line {} from {} to {}.",
+                    charger_id, time_range.from, time_range.to, reaching_line.1, reaching_range.from, reaching_range.to)));
+            } else if time_range.from > reaching_range.to {
+                findings.push((Check::ReportGap, *line, format!("Charger {} has no reports from {} to {}.",
+                    charger_id, reaching_range.to, time_range.from)));
+            }
+            if time_range.to <= reaching_range.to {
+                continue;
+            }
+        }
+        reaching = Some(report);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validate(input: &str) -> ValidationReport {
+        let mut validator = Validator::new();
+        validator.read_lines("input.txt", input.as_bytes()).unwrap();
+        validator.finish()
+    }
+
+    fn checks(report: &ValidationReport) -> Vec<(Check, usize)> {
+        report.findings.iter().map(|finding| (finding.check, finding.line)).collect()
+    }
+
+    #[test]
+    fn valid_input_has_no_findings() {
+        let report = validate("[Stations]\n0 1001\n[Charger Availability Reports]\n1001 0 50 true\n1001 50 100 false\n");
+        assert_eq!(report, ValidationReport::default());
+        assert_eq!(report.to_string(), "Summary: 0 error(s), 0 warning(s), 0 info(s)");
+    }
+
+    #[test]
+    fn finds_each_check() {
+        let input = "\
+[Stations]
+0 1001 1001
+1 1002
+0 1003
+2 A
+[Charger Availability Reports]
+1001 100 200 true
+1001 0 50 true
+1001 150 300 false
+1001 120 140 true
+1001 400 400 true
+1005 0 10 true
+";
+        let report = validate(input);
+        assert_eq!(checks(&report), vec![
+            (Check::InvalidLine, 5),
+            (Check::ConflictingWindows, 9),
+            (Check::DuplicateStation, 4),
+            (Check::DuplicateCharger, 2),
+            (Check::ChargerWithoutReports, 3),
+            (Check::ChargerWithoutReports, 4),
+            (Check::OrphanedCharger, 12),
+            (Check::ZeroLengthWindow, 11),
+            (Check::OverlappingWindows, 10),
+            (Check::ReportGap, 7),
+            (Check::NonMonotonicOrder, 8),
+            (Check::NonMonotonicOrder, 10),
+        ]);
+        assert_eq!((report.errors, report.warnings, report.infos), (2, 7, 3));
+        assert_eq!(report.findings[2].to_string(), "input.txt:4: warning[duplicate-station]: Station 0 is already listed on line 2.");
+        assert_eq!(report.counts["charger-without-reports"], 2);
+    }
+
+    #[test]
+    fn checks_span_files() {
+        let mut validator = Validator::new();
+        validator.read_lines("stations.txt", "[Stations]\n0 1001\n".as_bytes()).unwrap();
+        validator.read_lines("reports.txt", "[Stations]\n0 1002\n[Charger Availability Reports]\n1001 0 10 true\n1002 0 10 true\n".as_bytes()).unwrap();
+        let report = validator.finish();
+        assert_eq!(report.findings.len(), 1);
+        assert_eq!(report.findings[0].to_string(), "reports.txt:2: warning[duplicate-station]: Station 0 is already listed on line 2 of stations.txt.");
+    }
+}
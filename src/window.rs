@@ -0,0 +1,123 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::time_range::TimeRange;
+
+/// How time inside a `ReportingWindow` that no charger reported on counts.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnreportedTime {
+    /// Unreported time counts as down time. Uptime is computed over the
+    /// whole window.
+    #[default]
+    Down,
+    /// Unreported time is left out. Uptime is computed over the time covered
+    /// by at least one report inside the window.
+    Unknown
+}
+
+impl UnreportedTime {
+
+    /// Name of the option, as accepted by `from_str`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            UnreportedTime::Down => "down",
+            UnreportedTime::Unknown => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for UnreportedTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for UnreportedTime {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<UnreportedTime, String> {
+        match name {
+            "down" => Ok(UnreportedTime::Down),
+            "unknown" => Ok(UnreportedTime::Unknown),
+            _ => Err(format!("Unknown unreported time option '{}'. Expected one of: down, unknown.", name)),
+        }
+    }
+}
+
+/// A fixed window to compute uptime over, such as a calendar month.
+///
+/// The window is taken as `[from, to)`, like a `TimeRange`. Reports are clipped
+/// to the window, and reports entirely outside of it are left out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReportingWindow {
+    pub from: u64,
+    pub to: u64,
+    /// How unreported time inside the window counts.
+    pub unreported: UnreportedTime
+}
+
+impl ReportingWindow {
+
+    /// Creates a new `ReportingWindow`.
+    /// ### Input:
+    /// - `from`: Start time in nanoseconds (inclusive)
+    /// - `to`: End time in nanoseconds (exclusive)
+    /// - `unreported`: How unreported time inside the window counts
+    ///
+    /// ### Output:
+    /// - `Result<ReportingWindow, String>`: The window wrapped in `Ok()`, or an
+    ///   error message if `from` is not before `to`.
+    pub fn new(from: u64, to: u64, unreported: UnreportedTime) -> Result<ReportingWindow, String> {
+        if from>=to {
+            return Err(format!("Invalid reporting window from {} to {}. The start must be before the end.", from, to));
+        }
+        Ok(ReportingWindow { from, to, unreported })
+    }
+
+    /// Length of the window in nanoseconds.
+    pub fn duration(&self) -> u64 {
+        self.to - self.from
+    }
+
+    /// Clips windows sorted by start time to this reporting window, keeping
+    /// them in ascending order of start time. Windows entirely outside of it
+    /// are left out. Zero length windows are kept if they are inside it.
+    pub fn clip(&self, sorted_times: &[TimeRange]) -> Vec<TimeRange> {
+        sorted_times.iter()
+            .filter(|time_range| time_range.from < self.to
+                && (time_range.to > self.from || (time_range.from == time_range.to && time_range.from >= self.from)))
+            .map(|time_range| TimeRange { from: time_range.from.max(self.from), to: time_range.to.min(self.to), ..time_range.clone() })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clip_to_window() {
+        let window = ReportingWindow::new(100, 200, UnreportedTime::Down).unwrap();
+        let sorted_times = vec![
+            TimeRange::new(0, 100, true),
+            TimeRange::new(50, 150, false),
+            TimeRange::new(100, 100, true),
+            TimeRange::new(120, 130, true),
+            TimeRange::new(180, 300, true),
+            TimeRange::new(200, 300, false),
+        ];
+        assert_eq!(window.clip(&sorted_times), vec![
+            TimeRange::new(100, 150, false),
+            TimeRange::new(100, 100, true),
+            TimeRange::new(120, 130, true),
+            TimeRange::new(180, 200, true),
+        ]);
+    }
+
+    #[test]
+    fn window_must_not_be_empty() {
+        assert!(ReportingWindow::new(10, 10, UnreportedTime::Down).is_err());
+        assert_eq!("unknown".parse::<UnreportedTime>(), Ok(UnreportedTime::Unknown));
+        assert!("maybe".parse::<UnreportedTime>().is_err());
+    }
+}